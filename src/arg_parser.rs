@@ -2,7 +2,8 @@
 #[derive(Debug, Clone)]
 pub(crate) struct Arg<ID: Copy> {
     id: ID,
-    short_name: char,
+    short_name: Option<char>,
+    long_name: Option<String>,
     value_name: Option<String>,
     help: String,
 }
@@ -11,7 +12,24 @@ impl<ID: Copy> Arg<ID> {
     pub(crate) fn new(id: ID, short_name: char, value_name: Option<String>, help: String) -> Self {
         Arg {
             id,
-            short_name,
+            short_name: Some(short_name),
+            long_name: None,
+            value_name,
+            help,
+        }
+    }
+
+    /// Create the option that has only the long name, e.g. `--name`.
+    pub(crate) fn new_long(
+        id: ID,
+        long_name: &str,
+        value_name: Option<String>,
+        help: String,
+    ) -> Self {
+        Arg {
+            id,
+            short_name: None,
+            long_name: Some(long_name.to_string()),
             value_name,
             help,
         }
@@ -19,12 +37,23 @@ impl<ID: Copy> Arg<ID> {
 
     // Check if arg is matched to this argument option
     fn is_matched(&self, arg: &str) -> bool {
-        if arg.len() == 2 {
-            (arg.chars().nth(0) == Some('-')) && (arg.chars().nth(1) == Some(self.short_name))
+        if let Some(long_name) = arg.strip_prefix("--") {
+            self.long_name.as_deref() == Some(long_name)
+        } else if arg.len() == 2 {
+            (arg.chars().nth(0) == Some('-')) && (arg.chars().nth(1) == self.short_name)
         } else {
             false
         }
     }
+
+    /// Option name shown in the messages, e.g. `-a` or `--name`
+    fn display_name(&self) -> String {
+        match (&self.short_name, &self.long_name) {
+            (Some(short_name), _) => format!("-{}", short_name),
+            (None, Some(long_name)) => format!("--{}", long_name),
+            (None, None) => String::new(),
+        }
+    }
 }
 
 #[test]
@@ -32,6 +61,12 @@ fn test_arg_is_matched() {
     let arg = Arg::new(0, 'a', None, "help".to_string());
     assert_eq!(arg.is_matched("-a"), true);
     assert_eq!(arg.is_matched("-b"), false);
+
+    let arg = Arg::new_long(0, "abc", None, "help".to_string());
+    assert!(arg.is_matched("--abc"));
+    assert!(!arg.is_matched("--ab"));
+    assert!(!arg.is_matched("-a"));
+    assert_eq!(arg.display_name(), "--abc");
 }
 
 /// Argument value
@@ -46,19 +81,19 @@ pub(crate) enum ArgValue<ID: Copy + PartialEq> {
 pub(crate) enum ArgParseError {
     #[error("\'-{name}\': parameter value missing")]
     ArgValueMissing { name: char },
+    #[error("\'--{name}\': parameter value missing")]
+    LongArgValueMissing { name: String },
     #[error("\'-{argument}\': wrong argument")]
     ParseError { argument: String },
 }
 
 pub(crate) struct App<'a, ID: Copy + PartialEq> {
-    options: Box<&'a [Arg<ID>]>,
+    options: &'a [Arg<ID>],
 }
 
 impl<ID: Copy + PartialEq> App<'_, ID> {
     pub(crate) fn new(options: &[Arg<ID>]) -> App<'_, ID> {
-        App {
-            options: Box::new(options),
-        }
+        App { options }
     }
 
     /// Find the arg object for given argument text
@@ -80,7 +115,6 @@ impl<ID: Copy + PartialEq> App<'_, ID> {
                 values.push(ArgValue::Value { value: argument });
             } else if let Some(arg) = self.find_matched_arg(&argument) {
                 let id = arg.id;
-                let short_name = arg.short_name;
 
                 if arg.value_name.is_some() {
                     // if this option takes a value
@@ -89,8 +123,12 @@ impl<ID: Copy + PartialEq> App<'_, ID> {
                             id,
                             value: Some(val.to_string()),
                         });
+                    } else if let Some(name) = arg.short_name {
+                        return Err(ArgParseError::ArgValueMissing { name });
                     } else {
-                        return Err(ArgParseError::ArgValueMissing { name: short_name });
+                        return Err(ArgParseError::LongArgValueMissing {
+                            name: arg.long_name.clone().unwrap_or_default(),
+                        });
                     }
                 } else {
                     // if this option does not take a value
@@ -110,9 +148,9 @@ impl<ID: Copy + PartialEq> App<'_, ID> {
 
         for option in self.options.iter() {
             let left = if let Some(t) = &option.value_name {
-                format!("    -{} {}", option.short_name, t)
+                format!("    {} {}", option.display_name(), t)
             } else {
-                format!("    -{}", option.short_name)
+                format!("    {}", option.display_name())
             };
             text.push_str(left.as_str());
             let indent_offset = 30.max(left.len() + 1);
@@ -177,6 +215,30 @@ fn test_app_parse() {
     let matches = app.parse(&arguments);
     assert_eq!(matches, Err(ArgParseError::ArgValueMissing { name: 'b' }));
 
+    let options = vec![
+        Arg::new(0, 'a', None, "option a".to_string()),
+        Arg::new_long(1, "bb", Some("VAL".to_string()), "option bb".to_string()),
+    ];
+    let long_app = App::new(&options);
+    let arguments = vec!["--bb", "B1", "-a"];
+    let matches = long_app.parse(&arguments).unwrap();
+    assert_eq!(
+        matches[0],
+        ArgValue::Arg {
+            id: 1,
+            value: Some("B1".to_string())
+        }
+    );
+    assert_eq!(matches[1], ArgValue::Arg { id: 0, value: None });
+    let arguments = vec!["-a", "--bb"];
+    assert_eq!(
+        long_app.parse(&arguments),
+        Err(ArgParseError::LongArgValueMissing {
+            name: "bb".to_string()
+        })
+    );
+    assert!(long_app.help_option_message().contains("    --bb VAL"));
+
     let arguments = vec!["-b", "-a"];
     let matches = app.parse(&arguments).unwrap();
     assert_eq!(matches.len(), 1);
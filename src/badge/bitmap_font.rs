@@ -0,0 +1,601 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::badge::canvas::Canvas;
+
+#[derive(thiserror::Error, Debug)]
+pub enum BitmapFontError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error("invalid font data: {0}")]
+    InvalidFontData(String),
+}
+
+fn invalid(message: &str) -> BitmapFontError {
+    BitmapFontError::InvalidFontData(message.to_string())
+}
+
+/// Glyph bitmap placed relative to the origin on the baseline
+#[derive(Debug, Clone, PartialEq)]
+struct Glyph {
+    /// Distance to the origin of the next glyph
+    advance: i32,
+    /// Column of the left edge of the bitmap from the origin
+    left: i32,
+    /// Rows of the top edge of the bitmap above the baseline
+    top: i32,
+    bitmap: Canvas,
+}
+
+/// Font of the fixed size glyph bitmaps, i.e. BDF or PCF font
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BitmapFont {
+    ascent: i32,
+    descent: i32,
+    glyphs: HashMap<u32, Glyph>,
+    default_char: Option<u32>,
+}
+
+/// Font name to select the built-in font
+pub const BUILTIN_FONT_NAME: &str = "builtin";
+
+/// Public domain 6x12 "Fixed" font of X11 covering Latin-1, Latin Extended-A and common symbols
+const BUILTIN_FONT_BDF: &str = include_str!("fonts/6x12.bdf");
+
+/// Font built in the binary, which does not depend on the fonts installed in the system
+pub(crate) fn builtin_font() -> &'static BitmapFont {
+    static FONT: OnceLock<BitmapFont> = OnceLock::new();
+    FONT.get_or_init(|| BitmapFont::from_bdf(BUILTIN_FONT_BDF.as_bytes()).unwrap())
+}
+
+#[test]
+fn test_builtin_font() {
+    let font = builtin_font();
+    assert_eq!((font.ascent, font.descent), (10, 2));
+    for c in ['A', 'z', '~', 'é', 'ÿ', 'Ł', '€', '→', '♥', '★', '✓'] {
+        assert!(font.glyphs.contains_key(&(c as u32)), "{}", c);
+    }
+    let canvas = render(font, "Hi", 11);
+    assert_eq!((canvas.width, canvas.height), (12, 11));
+    assert!(canvas.pixels.iter().any(|&v| v != 0));
+
+    // the accents on the top row of the 12 px font are kept on the 11 px canvas
+    let lit_count = |canvas: &Canvas| canvas.pixels.iter().filter(|&&v| v != 0).count();
+    let glyph_lit_count = |c: char| {
+        let bitmap = &font.glyphs[&(c as u32)].bitmap;
+        bitmap.pixels.iter().filter(|&&v| v != 0).count()
+    };
+    let canvas = render(font, "ÉÅ", 11);
+    assert_eq!(
+        lit_count(&canvas),
+        glyph_lit_count('É') + glyph_lit_count('Å')
+    );
+    assert!((0..canvas.width).any(|x| canvas.get(x, 0)));
+    // "g" keeps its descender on the bottom row
+    let canvas = render(font, "g", 11);
+    assert_eq!(lit_count(&canvas), glyph_lit_count('g'));
+}
+
+/// Returns true if the file is a BDF or PCF font by the extension
+pub(crate) fn is_bitmap_font(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("bdf") || ext.eq_ignore_ascii_case("pcf"))
+}
+
+impl BitmapFont {
+    /// Load the BDF or PCF font file; the format is detected by the content.
+    pub(crate) fn load(path: &Path) -> Result<Self, BitmapFontError> {
+        let data = std::fs::read(path)?;
+        if data.starts_with(PCF_MAGIC) {
+            Self::from_pcf(&data)
+        } else {
+            Self::from_bdf(&data)
+        }
+    }
+
+    /// Parse the glyph bitmap distribution format (BDF) font
+    pub(crate) fn from_bdf(data: &[u8]) -> Result<Self, BitmapFontError> {
+        let text = String::from_utf8_lossy(data);
+        let mut lines = text.lines().map(str::trim);
+        if !lines
+            .next()
+            .is_some_and(|line| line.starts_with("STARTFONT"))
+        {
+            return Err(invalid("STARTFONT not found"));
+        }
+
+        let number = |v: Option<&str>| -> Result<i32, BitmapFontError> {
+            v.and_then(|v| v.parse().ok())
+                .ok_or_else(|| invalid("wrong number"))
+        };
+        let mut font = BitmapFont {
+            ascent: 0,
+            descent: 0,
+            glyphs: HashMap::new(),
+            default_char: None,
+        };
+        let mut bounding_box = None;
+        while let Some(line) = lines.next() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("FONTBOUNDINGBOX") => {
+                    let [_, height, _, y] = [0; 4].map(|_| number(tokens.next()));
+                    bounding_box = Some((height?, y?));
+                }
+                Some("FONT_ASCENT") => font.ascent = number(tokens.next())?,
+                Some("FONT_DESCENT") => font.descent = number(tokens.next())?,
+                Some("DEFAULT_CHAR") => font.default_char = Some(number(tokens.next())? as u32),
+                Some("STARTCHAR") => {
+                    let mut encoding = None;
+                    let mut advance = 0;
+                    let mut bbx = None;
+                    for line in lines.by_ref() {
+                        let mut tokens = line.split_whitespace();
+                        match tokens.next() {
+                            Some("ENCODING") => encoding = Some(number(tokens.next())?),
+                            Some("DWIDTH") => advance = number(tokens.next())?,
+                            Some("BBX") => {
+                                let [w, h, x, y] = [0; 4].map(|_| number(tokens.next()));
+                                bbx = Some((w?.max(0) as usize, h?.max(0) as usize, x?, y?));
+                            }
+                            Some("BITMAP") => break,
+                            _ => {}
+                        }
+                    }
+                    let (width, height, left, bottom) =
+                        bbx.ok_or_else(|| invalid("BBX not found"))?;
+                    let mut bitmap = Canvas::new(width, height);
+                    for y in 0..height {
+                        let row = lines.next().ok_or_else(|| invalid("truncated BITMAP"))?;
+                        for (i, digit) in row.chars().enumerate() {
+                            let nibble =
+                                digit.to_digit(16).ok_or_else(|| invalid("wrong BITMAP"))?;
+                            for bit in 0..4 {
+                                bitmap.set(i * 4 + bit, y, nibble & (0x8 >> bit) != 0);
+                            }
+                        }
+                    }
+                    // glyphs with the negative encoding are not mapped to any character
+                    if let Some(code) = encoding.filter(|&code| code >= 0) {
+                        let glyph = Glyph {
+                            advance,
+                            left,
+                            top: bottom + height as i32,
+                            bitmap,
+                        };
+                        font.glyphs.insert(code as u32, glyph);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if font.ascent == 0 && font.descent == 0 {
+            let (height, y) = bounding_box.ok_or_else(|| invalid("font height not found"))?;
+            font.ascent = height + y;
+            font.descent = -y;
+        }
+        Ok(font)
+    }
+
+    /// Parse the portable compiled format (PCF) font
+    pub(crate) fn from_pcf(data: &[u8]) -> Result<Self, BitmapFontError> {
+        let header = PcfReader::new(data, 0, false);
+        if !data.starts_with(PCF_MAGIC) {
+            return Err(invalid("not a pcf file"));
+        }
+        let table_count = header.u32(4)? as usize;
+        let mut tables = HashMap::new();
+        for i in 0..table_count {
+            let entry = 8 + i * 16;
+            tables.insert(header.u32(entry)?, header.u32(entry + 12)? as usize);
+        }
+        let table = |kind: u32| -> Result<PcfReader, BitmapFontError> {
+            let offset = *tables
+                .get(&kind)
+                .ok_or_else(|| invalid("pcf table not found"))?;
+            let format = PcfReader::new(data, offset, false).u32(0)?;
+            Ok(PcfReader::new(data, offset, format & PCF_BYTE_MASK != 0))
+        };
+
+        let accelerators = table(PCF_BDF_ACCELERATORS).or_else(|_| table(PCF_ACCELERATORS))?;
+        let ascent = accelerators.i32(12)?;
+        let descent = accelerators.i32(16)?;
+
+        // (left side bearing, right side bearing, advance, ascent, descent)
+        let metrics_table = table(PCF_METRICS)?;
+        let metrics = if metrics_table.format()? & PCF_FORMAT_MASK == PCF_COMPRESSED_METRICS {
+            let count = metrics_table.u16(4)? as usize;
+            (0..count)
+                .map(|i| {
+                    let [l, r, w, a, d] = [0, 1, 2, 3, 4]
+                        .map(|j| metrics_table.u8(6 + i * 5 + j).map(|v| v as i32 - 0x80));
+                    Ok((l?, r?, w?, a?, d?))
+                })
+                .collect::<Result<Vec<_>, BitmapFontError>>()?
+        } else {
+            let count = metrics_table.u32(4)? as usize;
+            (0..count)
+                .map(|i| {
+                    let [l, r, w, a, d] = [0, 1, 2, 3, 4]
+                        .map(|j| metrics_table.i16(8 + i * 12 + j * 2).map(i32::from));
+                    Ok((l?, r?, w?, a?, d?))
+                })
+                .collect::<Result<Vec<_>, BitmapFontError>>()?
+        };
+
+        let bitmaps = table(PCF_BITMAPS)?;
+        let format = bitmaps.format()?;
+        let glyph_count = bitmaps.u32(4)? as usize;
+        let data_offset = 8 + glyph_count * 4 + 16;
+        let row_pad = 1 << (format & 3);
+        let scan_unit = 1 << ((format >> 4) & 3);
+        let msb_bit_first = format & PCF_BIT_MASK != 0;
+        let msb_byte_first = format & PCF_BYTE_MASK != 0;
+        let bitmap =
+            |index: usize, width: usize, height: usize| -> Result<Canvas, BitmapFontError> {
+                let offset = data_offset + bitmaps.u32(8 + index * 4)? as usize;
+                let stride = width.div_ceil(8).next_multiple_of(row_pad);
+                let mut canvas = Canvas::new(width, height);
+                for y in 0..height {
+                    for x in 0..width {
+                        let mut byte = x / 8;
+                        if scan_unit > 1 && msb_bit_first != msb_byte_first {
+                            // bytes are swapped in each scan unit
+                            byte =
+                                byte / scan_unit * scan_unit + (scan_unit - 1 - byte % scan_unit);
+                        }
+                        let v = bitmaps.u8(offset + y * stride + byte)?;
+                        let mask = if msb_bit_first {
+                            0x80 >> (x % 8)
+                        } else {
+                            1 << (x % 8)
+                        };
+                        canvas.set(x, y, v & mask != 0);
+                    }
+                }
+                Ok(canvas)
+            };
+
+        let encodings = table(PCF_BDF_ENCODINGS)?;
+        let [min_byte2, max_byte2, min_byte1, max_byte1, default_char] =
+            [0, 1, 2, 3, 4].map(|i| encodings.i16(4 + i * 2).map(|v| v as u32));
+        let (min_byte2, max_byte2, min_byte1, max_byte1) =
+            (min_byte2?, max_byte2?, min_byte1?, max_byte1?);
+        let columns = max_byte2 + 1 - min_byte2;
+        let mut glyphs = HashMap::new();
+        for byte1 in min_byte1..=max_byte1 {
+            for byte2 in min_byte2..=max_byte2 {
+                let entry = ((byte1 - min_byte1) * columns + (byte2 - min_byte2)) as usize;
+                let index = encodings.u16(14 + entry * 2)? as usize;
+                if index == 0xFFFF {
+                    continue;
+                }
+                let &(l, r, w, a, d) = metrics
+                    .get(index)
+                    .ok_or_else(|| invalid("wrong glyph index"))?;
+                let glyph = Glyph {
+                    advance: w,
+                    left: l,
+                    top: a,
+                    bitmap: bitmap(index, (r - l).max(0) as usize, (a + d).max(0) as usize)?,
+                };
+                glyphs.insert((byte1 << 8) | byte2, glyph);
+            }
+        }
+
+        Ok(BitmapFont {
+            ascent,
+            descent,
+            glyphs,
+            default_char: Some(default_char?),
+        })
+    }
+
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&(c as u32))
+            .or_else(|| self.glyphs.get(&self.default_char?))
+    }
+
+    /// Returns true if the font has the glyph of the character
+    pub(crate) fn has_glyph(&self, c: char) -> bool {
+        self.glyphs.contains_key(&(c as u32))
+    }
+
+    /// Row of the baseline placed to center the font in `height`
+    pub(crate) fn baseline(&self, height: usize) -> i32 {
+        let font_height = self.ascent + self.descent;
+        if font_height <= height as i32 {
+            (height as i32 - font_height) / 2 + self.ascent
+        } else {
+            height as i32 - self.descent
+        }
+    }
+
+    /// Draw the glyph pixel-for-pixel with the origin at (`pen_x`, `baseline`)
+    ///
+    /// Returns the advance of the glyph. The glyph is only measured if `canvas` is `None`.
+    ///
+    /// If the font is taller than the canvas, a glyph sticking out of the top, such as
+    /// an accented capital letter, is moved down over its blank bottom rows to keep the pixels.
+    pub(crate) fn draw_glyph(
+        &self,
+        c: char,
+        canvas: Option<&mut Canvas>,
+        pen_x: i32,
+        baseline: i32,
+    ) -> i32 {
+        let Some(glyph) = self.glyph(c) else {
+            return 0;
+        };
+        if let Some(canvas) = canvas {
+            let lit_rows = (0..glyph.bitmap.height)
+                .filter(|&y| (0..glyph.bitmap.width).any(|x| glyph.bitmap.get(x, y)))
+                .map(|y| baseline - glyph.top + y as i32);
+            let (top, bottom) = lit_rows.fold((i32::MAX, i32::MIN), |(top, bottom), y| {
+                (top.min(y), bottom.max(y))
+            });
+            let shift = if top < 0 {
+                (-top).min(canvas.height as i32 - 1 - bottom).max(0)
+            } else {
+                0
+            };
+            for y in 0..glyph.bitmap.height {
+                for x in 0..glyph.bitmap.width {
+                    let canvas_x = pen_x.saturating_add(glyph.left + x as i32);
+                    let canvas_y = baseline - glyph.top + shift + y as i32;
+                    if glyph.bitmap.get(x, y) && canvas_x >= 0 && canvas_y >= 0 {
+                        canvas.set(canvas_x as usize, canvas_y as usize, true);
+                    }
+                }
+            }
+        }
+        glyph.advance.max(0)
+    }
+}
+
+#[cfg(test)]
+fn render(font: &BitmapFont, text: &str, height: usize) -> Canvas {
+    let width = text
+        .chars()
+        .map(|c| font.draw_glyph(c, None, 0, 0))
+        .sum::<i32>();
+    let mut canvas = Canvas::new(width as usize, height);
+    let baseline = font.baseline(height);
+    let mut pen_x = 0;
+    for c in text.chars() {
+        pen_x += font.draw_glyph(c, Some(&mut canvas), pen_x, baseline);
+    }
+    canvas
+}
+
+const PCF_MAGIC: &[u8] = b"\x01fcp";
+const PCF_ACCELERATORS: u32 = 1 << 1;
+const PCF_METRICS: u32 = 1 << 2;
+const PCF_BITMAPS: u32 = 1 << 3;
+const PCF_BDF_ENCODINGS: u32 = 1 << 5;
+const PCF_BDF_ACCELERATORS: u32 = 1 << 8;
+const PCF_FORMAT_MASK: u32 = 0xFFFF_FF00;
+const PCF_COMPRESSED_METRICS: u32 = 0x100;
+const PCF_BYTE_MASK: u32 = 1 << 2;
+const PCF_BIT_MASK: u32 = 1 << 3;
+
+/// Reader of the values in a pcf table
+struct PcfReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+}
+
+impl<'a> PcfReader<'a> {
+    fn new(data: &'a [u8], offset: usize, big_endian: bool) -> Self {
+        PcfReader {
+            data,
+            offset,
+            big_endian,
+        }
+    }
+
+    fn bytes<const N: usize>(&self, position: usize) -> Result<[u8; N], BitmapFontError> {
+        let start = self.offset + position;
+        let mut bytes: [u8; N] = self
+            .data
+            .get(start..start + N)
+            .ok_or_else(|| invalid("truncated pcf data"))?
+            .try_into()
+            .unwrap();
+        if !self.big_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    /// Format of the table, which is always little endian
+    fn format(&self) -> Result<u32, BitmapFontError> {
+        PcfReader::new(self.data, self.offset, false).u32(0)
+    }
+
+    fn u8(&self, position: usize) -> Result<u8, BitmapFontError> {
+        Ok(self.bytes::<1>(position)?[0])
+    }
+
+    fn u16(&self, position: usize) -> Result<u16, BitmapFontError> {
+        Ok(u16::from_be_bytes(self.bytes(position)?))
+    }
+
+    fn i16(&self, position: usize) -> Result<i16, BitmapFontError> {
+        Ok(i16::from_be_bytes(self.bytes(position)?))
+    }
+
+    fn u32(&self, position: usize) -> Result<u32, BitmapFontError> {
+        Ok(u32::from_be_bytes(self.bytes(position)?))
+    }
+
+    fn i32(&self, position: usize) -> Result<i32, BitmapFontError> {
+        Ok(i32::from_be_bytes(self.bytes(position)?))
+    }
+}
+
+#[cfg(test)]
+pub(crate) const TEST_BDF: &str = "STARTFONT 2.1
+FONT -test-fixed-medium-r-normal--5-50-75-75-c-40-iso10646-1
+SIZE 5 75 75
+FONTBOUNDINGBOX 3 5 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 4
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 2
+STARTCHAR A
+ENCODING 65
+SWIDTH 800 0
+DWIDTH 4 0
+BBX 3 4 0 0
+BITMAP
+40
+A0
+E0
+A0
+ENDCHAR
+STARTCHAR comma
+ENCODING 44
+SWIDTH 400 0
+DWIDTH 2 0
+BBX 1 2 0 -1
+BITMAP
+80
+80
+ENDCHAR
+ENDFONT
+";
+
+#[test]
+fn test_bitmap_font_from_bdf() {
+    assert!(is_bitmap_font(Path::new("font.BDF")));
+    assert!(is_bitmap_font(Path::new("font.pcf")));
+    assert!(!is_bitmap_font(Path::new("font.ttf")));
+
+    let font = BitmapFont::from_bdf(TEST_BDF.as_bytes()).unwrap();
+    assert_eq!((font.ascent, font.descent), (4, 1));
+    assert_eq!(font.glyphs.len(), 2);
+
+    // 5 rows font centered in 11 rows: the baseline is under the 7th row
+    let canvas = render(&font, "A,?", 11);
+    assert_eq!((canvas.width, canvas.height), (6, 11));
+    let lit = (0..11)
+        .flat_map(|y| (0..6).map(move |x| (x, y)))
+        .filter(|&(x, y)| canvas.get(x, y))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lit,
+        [
+            (1, 3),
+            (0, 4),
+            (2, 4),
+            (0, 5),
+            (1, 5),
+            (2, 5),
+            (0, 6),
+            (2, 6),
+            (4, 6),
+            (4, 7)
+        ]
+    );
+
+    assert!(BitmapFont::from_bdf(b"FONT x").is_err());
+}
+
+#[cfg(test)]
+fn pcf_from_bdf(font: &BitmapFont) -> Vec<u8> {
+    // big endian, MSB first, rows padded to 4 bytes with the uncompressed metrics
+    let format = PCF_BYTE_MASK | PCF_BIT_MASK | 2;
+    let mut codes = font.glyphs.keys().copied().collect::<Vec<_>>();
+    codes.sort();
+    let glyphs = codes
+        .iter()
+        .map(|code| &font.glyphs[code])
+        .collect::<Vec<_>>();
+
+    let mut accelerators = format.to_le_bytes().to_vec();
+    accelerators.extend([0; 8]);
+    accelerators.extend(font.ascent.to_be_bytes());
+    accelerators.extend(font.descent.to_be_bytes());
+
+    let mut metrics = format.to_le_bytes().to_vec();
+    metrics.extend((glyphs.len() as u32).to_be_bytes());
+    let mut bitmap_data = Vec::new();
+    let mut offsets = Vec::new();
+    for glyph in &glyphs {
+        let width = glyph.bitmap.width as i32;
+        let descent = glyph.bitmap.height as i32 - glyph.top;
+        for v in [
+            glyph.left,
+            glyph.left + width,
+            glyph.advance,
+            glyph.top,
+            descent,
+            0,
+        ] {
+            metrics.extend((v as i16).to_be_bytes());
+        }
+        offsets.push(bitmap_data.len() as u32);
+        for y in 0..glyph.bitmap.height {
+            let mut row = [0u8; 4];
+            for x in 0..glyph.bitmap.width {
+                if glyph.bitmap.get(x, y) {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            bitmap_data.extend(row);
+        }
+    }
+    let mut bitmaps = format.to_le_bytes().to_vec();
+    bitmaps.extend((glyphs.len() as u32).to_be_bytes());
+    offsets.iter().for_each(|v| bitmaps.extend(v.to_be_bytes()));
+    [0, 0, bitmap_data.len() as u32, 0]
+        .iter()
+        .for_each(|v| bitmaps.extend(v.to_be_bytes()));
+    bitmaps.extend(bitmap_data);
+
+    let mut encodings = format.to_le_bytes().to_vec();
+    for v in [0, 0xFF, 0, 0, font.default_char.unwrap_or(0) as i16] {
+        encodings.extend(v.to_be_bytes());
+    }
+    for code in 0..=0xFF {
+        let index = codes
+            .iter()
+            .position(|&c| c == code)
+            .map_or(0xFFFF, |i| i as u16);
+        encodings.extend(index.to_be_bytes());
+    }
+
+    let tables = [
+        (PCF_ACCELERATORS, accelerators),
+        (PCF_METRICS, metrics),
+        (PCF_BITMAPS, bitmaps),
+        (PCF_BDF_ENCODINGS, encodings),
+    ];
+    let mut pcf = PCF_MAGIC.to_vec();
+    pcf.extend((tables.len() as u32).to_le_bytes());
+    let mut offset = 8 + tables.len() * 16;
+    for (kind, table) in &tables {
+        for v in [*kind, format, table.len() as u32, offset as u32] {
+            pcf.extend(v.to_le_bytes());
+        }
+        offset += table.len();
+    }
+    tables.iter().for_each(|(_, table)| pcf.extend(table));
+    pcf
+}
+
+#[test]
+fn test_bitmap_font_from_pcf() {
+    let bdf = BitmapFont::from_bdf(TEST_BDF.as_bytes()).unwrap();
+    let pcf = BitmapFont::from_pcf(&pcf_from_bdf(&bdf)).unwrap();
+    assert_eq!((pcf.ascent, pcf.descent), (bdf.ascent, bdf.descent));
+    assert_eq!(render(&pcf, "A,A", 11), render(&bdf, "A,A", 11));
+
+    assert!(BitmapFont::from_pcf(PCF_MAGIC).is_err());
+}
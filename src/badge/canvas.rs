@@ -0,0 +1,202 @@
+use std::ops::Range;
+
+/// Monochrome pixel canvas; a non-zero pixel is a lit LED.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        let pixels = vec![0; width * height];
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Returns true if the pixel at (x, y) is lit.
+    pub(crate) fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels[y * self.width + x] != 0
+    }
+
+    /// Light up or clear the pixel at (x, y). Out-of-canvas positions are ignored.
+    pub(crate) fn set(&mut self, x: usize, y: usize, lit: bool) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = lit as u8;
+        }
+    }
+
+    /// Returns true if no pixel of the column `x` is lit.
+    pub(crate) fn is_blank_column(&self, x: usize) -> bool {
+        (0..self.height).all(|y| !self.get(x, y))
+    }
+
+    /// Range of the columns which contain at least one lit pixel.
+    pub(crate) fn content_columns(&self) -> Option<Range<usize>> {
+        let start = (0..self.width).find(|&x| !self.is_blank_column(x))?;
+        let end = (0..self.width).rfind(|&x| !self.is_blank_column(x))? + 1;
+        Some(start..end)
+    }
+
+    /// Split the columns up to the last lit one into pieces at most `max_width` wide.
+    ///
+    /// If `at_blank` is true, each piece ends at the last blank column within the width
+    /// if any, and the blank columns at the cut are dropped.
+    pub(crate) fn split_columns(&self, max_width: usize, at_blank: bool) -> Vec<Range<usize>> {
+        let max_width = max_width.max(1);
+        let end = self.content_columns().map_or(0, |columns| columns.end);
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while start < end {
+            let mut cut = (start + max_width).min(end);
+            if at_blank && cut < end {
+                if let Some(x) = (start + 1..=cut).rev().find(|&x| self.is_blank_column(x)) {
+                    cut = x;
+                }
+                while cut > start + 1 && self.is_blank_column(cut - 1) {
+                    cut -= 1;
+                }
+            }
+            pieces.push(start..cut);
+            start = cut;
+            if at_blank {
+                while start < end && self.is_blank_column(start) {
+                    start += 1;
+                }
+            }
+        }
+        pieces
+    }
+
+    /// Copy the columns `columns` of `src` to this canvas, starting at column `x`.
+    pub(crate) fn blit_columns(&mut self, src: &Canvas, columns: Range<usize>, x: usize) {
+        for (i, src_x) in columns.enumerate() {
+            for y in 0..self.height.min(src.height) {
+                if src.get(src_x, y) {
+                    self.set(x + i, y, true);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_canvas_content_columns() {
+    let mut canvas = Canvas::new(10, 2);
+    assert_eq!(canvas.content_columns(), None);
+    canvas.set(3, 0, true);
+    canvas.set(6, 1, true);
+    assert_eq!(canvas.content_columns(), Some(3..7));
+    assert!(canvas.get(3, 0));
+    assert!(!canvas.get(3, 1));
+    assert!(!canvas.get(10, 0));
+}
+
+#[test]
+fn test_canvas_split_columns() {
+    // lit columns: 0-2, 4-5, 9
+    let mut canvas = Canvas::new(16, 1);
+    for x in [0, 1, 2, 4, 5, 9] {
+        canvas.set(x, 0, true);
+    }
+    assert_eq!(canvas.split_columns(4, false), vec![0..4, 4..8, 8..10]);
+    assert_eq!(canvas.split_columns(4, true), vec![0..3, 4..6, 9..10]);
+    assert_eq!(canvas.split_columns(2, true), vec![0..2, 2..3, 4..6, 9..10]);
+    assert_eq!(canvas.split_columns(16, true), vec![0..10]);
+    assert_eq!(Canvas::new(16, 1).split_columns(4, true), vec![]);
+}
+
+#[test]
+fn test_canvas_blit_columns() {
+    let mut src = Canvas::new(4, 2);
+    src.set(1, 0, true);
+    src.set(2, 1, true);
+    let mut canvas = Canvas::new(6, 2);
+    canvas.blit_columns(&src, 1..3, 4);
+    #[rustfmt::skip]
+    assert_eq!(canvas.pixels, vec![
+        0, 0, 0, 0, 1, 0,
+        0, 0, 0, 0, 0, 1,
+    ]);
+}
+
+/// Convert the canvas data into the led badge message data.
+pub(crate) fn canvas2vec(canvas: &Canvas) -> Vec<u8> {
+    let data_width = canvas.width.div_ceil(8);
+    let data_height = canvas.height;
+
+    let mut data = vec![0; data_width * data_height];
+    for (i, &v) in canvas.pixels.iter().enumerate() {
+        if v > 0 {
+            let canvas_x = i % canvas.width;
+            let canvas_y = i / canvas.width;
+
+            let data_x = canvas_x / 8;
+            let data_offset = canvas_x % 8;
+            let data_y = canvas_y;
+            let data_index = data_x * canvas.height + data_y;
+
+            data[data_index] |= 0x80u8 >> data_offset as u8;
+        }
+    }
+
+    data
+}
+
+#[test]
+fn test_canvas2vec() {
+    let vec = vec![0b10101010, 0b11001100, 0b11110000, 0b11111111];
+    let canvas = Canvas {
+        #[rustfmt::skip]
+        pixels: vec![
+            1, 0, 1, 0, 1, 0, 1, 0, 1, 1, 1, 1, 0, 0, 0, 0,
+            1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1,
+        ],
+        width: 16,
+        height: 2,
+    };
+    assert_eq!(canvas2vec(&canvas), vec);
+
+    let vec = vec![
+        0b10101010, 0b11001100, 0b11110000, 0b11111111, 0b10000000, 0b10000000,
+    ];
+    let canvas = Canvas {
+        #[rustfmt::skip]
+        pixels: vec![
+            1, 0, 1, 0, 1, 0, 1, 0, 1, 1, 1, 1, 0, 0, 0, 0, 1,
+            1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        ],
+        width: 17,
+        height: 2,
+    };
+    assert_eq!(canvas2vec(&canvas), vec);
+}
+
+/// Convert the led badge message data into the canvas.
+pub(crate) fn vec2canvas(data: &[u8], height: usize) -> Canvas {
+    let mut canvas = Canvas::new(8 * data.len() / height, height);
+    for (data_index, &v) in data.iter().enumerate() {
+        let data_x = (data_index / height) * 8;
+        let data_y = data_index % height;
+        for i in 0..8usize {
+            if v & (0x80u8 >> i) != 0 {
+                canvas.set(data_x + i, data_y, true);
+            }
+        }
+    }
+    canvas
+}
+
+#[test]
+fn test_vec2canvas() {
+    let vec = vec![
+        0b10101010, 0b11001100, 0b11110000, 0b11111111, 0b10000000, 0b10000000,
+    ];
+    let canvas = vec2canvas(&vec, 2);
+    assert_eq!((canvas.width, canvas.height), (24, 2));
+    assert_eq!(canvas2vec(&canvas), vec);
+}
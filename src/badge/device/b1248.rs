@@ -9,6 +9,9 @@ const BADGE_VID: u16 = 0x0483;
 /// Product ID of the LED Badge
 const BADGE_PID: u16 = 0x5750;
 
+/// Number of the LED columns of the B1248 badge
+pub(crate) const B1248_VISIBLE_WIDTH: usize = 48;
+
 /// Message Offset/Length information in the Badge Protocol Configuration (second report to send)
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
@@ -1,3 +1,7 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
 use crate::badge::{Badge, BadgeError};
 
 mod b1248;
@@ -5,13 +9,59 @@ mod s1144;
 
 /// Badge type
 #[derive(Debug, PartialEq, Copy, Clone)]
-#[allow(dead_code)]
 pub enum BadgeType {
     Auto = 0,
     S1144,
     B1248,
 }
 
+impl BadgeType {
+    pub fn values() -> impl Iterator<Item = BadgeType> {
+        [BadgeType::Auto, BadgeType::S1144, BadgeType::B1248].into_iter()
+    }
+
+    /// Number of the LED columns visible on the device
+    ///
+    /// `Auto` gives the narrowest one so that the message fits on any device.
+    pub fn visible_width(&self) -> usize {
+        match self {
+            BadgeType::Auto => s1144::S1144_VISIBLE_WIDTH.min(b1248::B1248_VISIBLE_WIDTH),
+            BadgeType::S1144 => s1144::S1144_VISIBLE_WIDTH,
+            BadgeType::B1248 => b1248::B1248_VISIBLE_WIDTH,
+        }
+    }
+}
+
+#[test]
+fn test_badge_type_visible_width() {
+    assert_eq!(BadgeType::S1144.visible_width(), 44);
+    assert_eq!(BadgeType::B1248.visible_width(), 48);
+    assert_eq!(BadgeType::Auto.visible_width(), 44);
+}
+
+impl fmt::Display for BadgeType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(format!("{:?}", self).to_lowercase().as_str())
+    }
+}
+
+impl FromStr for BadgeType {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        BadgeType::values()
+            .find(|&v| v.to_string().as_str() == value)
+            .ok_or(())
+    }
+}
+
+#[test]
+fn test_badge_type_from_str() {
+    assert_eq!(BadgeType::from_str("b1248").unwrap(), BadgeType::B1248);
+    assert_eq!(BadgeType::from_str("auto").unwrap(), BadgeType::Auto);
+    assert_eq!(BadgeType::from_str("b1249"), Err(()));
+}
+
 /// Find device and then send the context information to the device
 ///
 /// # Errors
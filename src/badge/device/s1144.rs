@@ -9,6 +9,9 @@ const BADGE_VID: u16 = 0x0416;
 /// Product ID of the LED Badge
 const BADGE_PID: u16 = 0x5020;
 
+/// Number of the LED columns of the S1144 badge
+pub(crate) const S1144_VISIBLE_WIDTH: usize = 44;
+
 /// Badge Protocol Header (first report to send)
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
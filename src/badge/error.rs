@@ -3,6 +3,7 @@ use core::fmt::Debug;
 use freetype::Error as FtError;
 use hidapi::HidError;
 
+use crate::badge::bitmap_font::BitmapFontError;
 use crate::badge::font_selector::FontSelectorError;
 use crate::badge::image_io::{BadgeImageReadError, BadgeImageWriteError};
 
@@ -44,17 +45,38 @@ pub enum BadgeError {
     /// Font Loading Error
     #[error("Failed to load font: {0}")]
     FontLoading(FtError),
+    /// Bitmap Font Loading Error
+    #[error("Failed to load bitmap font: {source_error}{path_info}", source_error = .1, path_info = format_io_error_path(.0))]
+    BitmapFontLoading(Option<String>, #[source] BitmapFontError),
     /// File IO Error
     #[error("File IO Error: {source_error}{path_info}", source_error = .1, path_info = format_io_error_path(.0))]
     FileIo(Option<String>, #[source] std::io::Error),
     /// Png Reading Error
     #[error("Could not read the png file: {source_error}{path_info}", source_error = .1, path_info = format_io_error_path(.0))]
     PngReadError(Option<String>, #[source] BadgeImageReadError),
+    /// Image Reading Error
+    #[error("Could not read the image file: {source_error}{path_info}", source_error = .1, path_info = format_io_error_path(.0))]
+    ImageReadError(Option<String>, #[source] BadgeImageReadError),
     #[error("Could not write the png file: {source_error}{path_info}", source_error = .1, path_info = format_io_error_path(.0))]
     PngWriteError(Option<String>, #[source] BadgeImageWriteError),
+    /// Image Writing Error
+    #[error("Could not write the image file: {source_error}{path_info}", source_error = .1, path_info = format_io_error_path(.0))]
+    ImageWriteError(Option<String>, #[source] BadgeImageWriteError),
     /// No data to write
     #[error("No data to write")]
     NoDataToWrite,
+    /// Wrong size of the animation frame
+    #[error("Wrong size of frame #{0} ({1}x{2})")]
+    WrongFrameSize(usize, usize, usize),
+    /// Split message does not fit in the remaining slots
+    #[error("Message #{0} needs {1} slots to be split")]
+    TooManySlots(usize, usize),
+    /// Cells of the image grid do not fit in the remaining slots
+    #[error("{0}x{1} grid cells do not fit in the slots from message #{2}")]
+    TooManyGridCells(usize, usize, usize),
+    /// Messages exceed the display memory of the device
+    #[error("Messages exceed the device memory ({0} of {1} bytes)")]
+    DeviceMemoryExceeded(usize, usize),
 }
 
 impl From<hidapi::HidError> for BadgeError {
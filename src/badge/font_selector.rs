@@ -1,7 +1,7 @@
 use std::ffi::CString;
 use std::path::PathBuf;
 
-use fontconfig::{Fontconfig, Pattern};
+use fontconfig::{CharSet, Fontconfig, Pattern};
 
 /// Describes font finder error
 #[derive(thiserror::Error, Debug, Clone)]
@@ -48,6 +48,87 @@ pub(crate) fn select_font(
     }
 }
 
+/// Language of the character to choose the glyph of the right locale, e.g. Han characters
+fn lang_hint(c: char) -> Option<String> {
+    let lang = match c as u32 {
+        0x0370..=0x03FF => "el",
+        0x0400..=0x04FF => "ru",
+        0x0590..=0x05FF => "he",
+        0x0600..=0x06FF => "ar",
+        0x0900..=0x097F => "hi",
+        0x0E00..=0x0E7F => "th",
+        0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => "ja",
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => "ko",
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF => {
+            // Han characters are shared by the languages; prefer the one of the locale
+            let locale = std::env::var("LC_ALL")
+                .or_else(|_| std::env::var("LANG"))
+                .unwrap_or_default()
+                .to_lowercase();
+            [
+                ("ja", "ja"),
+                ("ko", "ko"),
+                ("zh_tw", "zh-tw"),
+                ("zh_hk", "zh-hk"),
+            ]
+            .into_iter()
+            .find(|(prefix, _)| locale.starts_with(prefix))
+            .map_or("zh-cn", |(_, lang)| lang)
+        }
+        _ => return None,
+    };
+    Some(lang.to_string())
+}
+
+/// Select font covering the character, preferring the font names
+///
+/// # Errors
+///
+/// Return Err if fontconfig has no font
+pub(crate) fn select_font_for_char(
+    c: char,
+    font_names: &[&str],
+    font_size: Option<usize>,
+) -> Result<(PathBuf, usize), FontSelectorError> {
+    let fc = Fontconfig::new().ok_or(FontSelectorError::FontConfigError)?;
+
+    let mut pattern = Pattern::new(&fc);
+    for &font_name in font_names {
+        let name_cstr = CString::new("family").unwrap();
+        let font_name_cstr = CString::new(font_name).unwrap();
+        pattern.add_string(name_cstr.as_c_str(), font_name_cstr.as_c_str());
+    }
+    let mut charset = CharSet::new(&fc);
+    charset.add_char(c);
+    pattern.add_charset(charset);
+    if let Some(lang) = lang_hint(c) {
+        let name_cstr = CString::new("lang").unwrap();
+        let lang_cstr = CString::new(lang).unwrap();
+        pattern.add_string(name_cstr.as_c_str(), lang_cstr.as_c_str());
+    }
+    if let Some(size) = font_size {
+        let name_cstr = CString::new("size").unwrap();
+        pattern.add_integer(name_cstr.as_c_str(), size as i32);
+    }
+
+    let font_match = pattern.font_match();
+    if let (Some(filename), Some(index)) = (font_match.filename(), font_match.face_index()) {
+        Ok((PathBuf::from(filename), index as usize))
+    } else {
+        Err(FontSelectorError::FontNotFound(format!(
+            "U+{:04X}",
+            c as u32
+        )))
+    }
+}
+
+#[test]
+fn test_select_font_for_char() {
+    assert_eq!(lang_hint('あ').as_deref(), Some("ja"));
+    assert_eq!(lang_hint('A'), None);
+    assert!(select_font_for_char('A', &["Liberation Sans"], Some(11)).is_ok());
+}
+
 #[test]
 fn test_select_font() {
     assert!(matches!(
@@ -0,0 +1,90 @@
+use std::io::Write;
+
+use crate::badge::canvas::canvas2vec;
+use crate::badge::image_io::coalesce_frames;
+use crate::badge::simulator::simulate_message;
+use crate::badge::{BADGE_MSG_FONT_HEIGHT, Badge};
+
+/// Page template with the player; `/*BADGE_DATA*/null` is replaced by the badge data
+const HTML_TEMPLATE: &str = include_str!("html_preview.html");
+
+/// Time step of the simulation in milliseconds; the intervals of the effects are its multiples.
+const TIME_STEP_MS: u32 = 25;
+
+/// Serialize the badge messages into the JSON object read by the player
+///
+/// Each message holds the display images simulated for a play with their durations,
+/// so that the page shows the same as the simulation written by the other formats.
+fn badge_to_json(badge: &Badge, width: usize) -> String {
+    let messages = badge
+        .messages
+        .iter()
+        .map(|message| {
+            let simulated = simulate_message(message, width, BADGE_MSG_FONT_HEIGHT, TIME_STEP_MS);
+            let frames = coalesce_frames(&simulated, TIME_STEP_MS)
+                .into_iter()
+                .map(|(frame, duration)| {
+                    let data = canvas2vec(frame)
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    format!(r#"{{"duration":{},"data":[{}]}}"#, duration, data)
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            format!(
+                r#"{{"effect":"{}","speed":{},"blink":{},"frame":{},"frames":[{}]}}"#,
+                message.effect, message.speed, message.blink, message.frame, frames
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        r#"{{"width":{},"height":{},"messages":[{}]}}"#,
+        width, BADGE_MSG_FONT_HEIGHT, messages
+    )
+}
+
+/// Write the self-contained html page which plays the messages of the badge
+pub(crate) fn write_html_preview<W: Write>(
+    badge: &Badge,
+    width: usize,
+    mut writer: W,
+) -> std::io::Result<()> {
+    let html = HTML_TEMPLATE.replace("/*BADGE_DATA*/null", &badge_to_json(badge, width));
+    writer.write_all(html.as_bytes())?;
+    writer.flush()
+}
+
+#[test]
+fn test_write_html_preview() {
+    use crate::badge::BadgeEffect;
+
+    let mut badge = Badge::new().unwrap();
+    badge.messages[0].data = vec![0x80; BADGE_MSG_FONT_HEIGHT];
+    badge.messages[0].effect = BadgeEffect::Freeze;
+    badge.messages[0].blink = true;
+
+    // the still page blinks every 500 ms during the 10 steps of 200 ms
+    let json = badge_to_json(&badge, 16);
+    let lit = r#"{"duration":500,"data":[128,128,128,128,128,128,128,128,128,128,128,0,0,0,0,0,0,0,0,0,0,0]}"#;
+    let unlit = r#"{"duration":500,"data":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}"#;
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"width":16,"height":11,"messages":[{{"effect":"freeze","speed":1,"blink":true,"frame":false,"frames":[{},{},{},{}]}},{}]}}"#,
+            lit,
+            unlit,
+            lit,
+            unlit,
+            [r#"{"effect":"left","speed":1,"blink":false,"frame":false,"frames":[]}"#; 7].join(",")
+        )
+    );
+
+    let mut output = Vec::new();
+    write_html_preview(&badge, 16, &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("<!DOCTYPE html>"));
+    assert!(output.contains(&format!("const BADGE = {};", json)));
+}
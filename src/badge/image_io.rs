@@ -1,27 +1,58 @@
+use std::fmt;
 #[cfg(test)]
 use std::io::Cursor;
 use std::io::{BufRead, Read, Seek, Write};
+use std::str::FromStr;
 
-use png::{BitDepth, ColorType, Decoder, DecodingError, Encoder, EncodingError};
+use png::{
+    BitDepth, BlendOp, ColorType, Decoder, DecodingError, DisposeOp, Encoder, EncodingError, Info,
+    OutputInfo, Transformations,
+};
 
 use crate::badge::BADGE_MSG_FONT_HEIGHT;
+use crate::badge::canvas::{Canvas, canvas2vec, vec2canvas};
+use crate::badge::led_render::Color;
+use crate::badge::raster::{DitherMethod, GrayChannel, RgbaImage};
+use crate::badge::svg;
 
 #[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum BadgeImageWriteError {
     #[error(transparent)]
     PngEncodeError(#[from] EncodingError),
+    #[error(transparent)]
+    GifEncodeError(#[from] gif::EncodingError),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum BadgeImageReadError {
     #[error(transparent)]
     PngDecodeError(#[from] DecodingError),
-    #[error("{}", .0)]
-    UnsupportedPngError(String),
+    #[error(transparent)]
+    GifDecodeError(#[from] gif::DecodingError),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error("unknown image format")]
+    UnknownImageFormat,
+    #[error("{0}")]
+    InvalidImageData(String),
 }
 
+/// PNG file signature
+const PNG_SIGNATURE: &[u8] = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// GIF file signature (common part of "GIF87a" and "GIF89a")
+const GIF_SIGNATURE: &[u8] = b"GIF8";
+
+/// Upper limit of the pixels of the decoded image, checked before allocating it
+const MAX_IMAGE_PIXELS: usize = 1 << 24;
+
+/// Write the message data as png image with the text chunks of (keyword, text)
 pub fn write_badge_message_to_png<W: Write>(
     message_data: &[u8],
+    text_chunks: &[(&str, String)],
     writer: W,
 ) -> Result<(), BadgeImageWriteError> {
     let (width, height) = (
@@ -43,6 +74,14 @@ pub fn write_badge_message_to_png<W: Write>(
     let mut encoder = Encoder::new(writer, width as u32, height as u32);
     encoder.set_color(ColorType::Grayscale);
     encoder.set_depth(BitDepth::Eight);
+    for (keyword, text) in text_chunks {
+        // tEXt is Latin-1; use iTXt for the other characters
+        if text.is_ascii() {
+            encoder.add_text_chunk(keyword.to_string(), text.clone())?;
+        } else {
+            encoder.add_itxt_chunk(keyword.to_string(), text.clone())?;
+        }
+    }
     let mut writer = encoder.write_header()?;
     writer.write_image_data(&image_data)?;
     Ok(())
@@ -53,7 +92,7 @@ fn test_write_badge_message_to_png() {
     let mut png_data = Vec::<u8>::new();
     let empty_message_data = &[];
     let mut w = Cursor::new(&mut png_data);
-    assert!(write_badge_message_to_png(empty_message_data, w.get_mut()).is_err());
+    assert!(write_badge_message_to_png(empty_message_data, &[], w.get_mut()).is_err());
 
     #[rustfmt::skip]
     let sample_data: [u8; 22] = [
@@ -78,7 +117,7 @@ fn test_write_badge_message_to_png() {
     let mut png_data = Vec::<u8>::new();
     let mut w = Cursor::new(&mut png_data);
     assert!(matches!(
-        write_badge_message_to_png(&sample_data, w.get_mut()),
+        write_badge_message_to_png(&sample_data, &[], w.get_mut()),
         Ok(())
     ));
 
@@ -103,52 +142,444 @@ fn test_write_badge_message_to_png() {
     assert_eq!(png_pixels, sample_pixels);
 }
 
-pub fn read_png_to_badge_message<R: Read + BufRead + Seek>(
-    reader: R,
-) -> Result<Vec<u8>, BadgeImageReadError> {
-    let decoder = Decoder::new(reader);
-    let mut reader = decoder.read_info()?;
-    let info = reader.info().clone();
+pub fn write_rgba_to_png<W: Write>(
+    image: &RgbaImage,
+    writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let mut encoder = Encoder::new(writer, image.width as u32, image.height as u32);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.pixels.as_flattened())?;
+    Ok(())
+}
 
-    if info.bit_depth != BitDepth::Eight {
-        return Err(BadgeImageReadError::UnsupportedPngError(
-            format!("{:?}: only 8bpp PNG supported", info.bit_depth).to_string(),
-        ));
+#[test]
+fn test_write_rgba_to_png() {
+    let mut image = RgbaImage::new(2, 1);
+    image.pixels[1] = [0x10, 0x20, 0x30, 0xFF];
+    let mut png_data = Vec::new();
+    write_rgba_to_png(&image, &mut png_data).unwrap();
+    assert_eq!(
+        read_apng_frames(Cursor::new(&png_data), |frame| Ok(frame.clone())).unwrap(),
+        vec![image]
+    );
+}
+
+/// Merge the consecutive identical frames and return each frame with its duration.
+pub(crate) fn coalesce_frames(frames: &[Canvas], delay_ms: u32) -> Vec<(&Canvas, u32)> {
+    let mut coalesced: Vec<(&Canvas, u32)> = Vec::with_capacity(frames.len());
+    for frame in frames {
+        match coalesced.last_mut() {
+            Some((last, duration)) if *last == frame => *duration += delay_ms,
+            _ => coalesced.push((frame, delay_ms)),
+        }
     }
-    if info.height != BADGE_MSG_FONT_HEIGHT as u32 {
-        return Err(BadgeImageReadError::UnsupportedPngError(
-            format!(
-                "height must be {}px, but height is {}",
-                BADGE_MSG_FONT_HEIGHT, info.height
-            )
-            .to_string(),
-        ));
+    coalesced
+}
+
+#[test]
+fn test_coalesce_frames() {
+    let (a, b) = (Canvas::new(1, 1), Canvas::new(2, 1));
+    let frames = [a.clone(), a.clone(), b.clone(), a.clone()];
+    assert_eq!(
+        coalesce_frames(&frames, 10),
+        vec![(&a, 20), (&b, 10), (&a, 10)]
+    );
+}
+
+pub fn write_animation_to_gif<W: Write>(
+    frames: &[Canvas],
+    delay_ms: u32,
+    writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let (width, height) = frames
+        .first()
+        .map_or((0, 0), |frame| (frame.width, frame.height));
+    let palette = [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF];
+    let mut encoder = gif::Encoder::new(writer, width as u16, height as u16, &palette)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for (frame, duration) in coalesce_frames(frames, delay_ms) {
+        let gif_frame = gif::Frame {
+            width: width as u16,
+            height: height as u16,
+            delay: (duration / 10).min(u16::MAX as u32) as u16,
+            buffer: std::borrow::Cow::Borrowed(&frame.pixels),
+            ..Default::default()
+        };
+        encoder.write_frame(&gif_frame)?;
+    }
+    Ok(())
+}
+
+pub fn write_animation_to_png<W: Write>(
+    frames: &[Canvas],
+    delay_ms: u32,
+    writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let (width, height) = frames
+        .first()
+        .map_or((0, 0), |frame| (frame.width, frame.height));
+    let coalesced = coalesce_frames(frames, delay_ms);
+    let mut encoder = Encoder::new(writer, width as u32, height as u32);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(BitDepth::Eight);
+    encoder.set_animated(coalesced.len() as u32, 0)?;
+    let mut writer = encoder.write_header()?;
+    for (frame, duration) in coalesced {
+        writer.set_frame_delay(duration.min(u16::MAX as u32) as u16, 1000)?;
+        let image_data = frame.pixels.iter().map(|&v| v * 0xFF).collect::<Vec<_>>();
+        writer.write_image_data(&image_data)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_write_animation() {
+    let mut frames = vec![Canvas::new(3, 2); 3];
+    frames[2].set(1, 1, true);
+
+    let mut gif_data = Vec::new();
+    write_animation_to_gif(&frames, 50, &mut gif_data).unwrap();
+    let mut decoder = gif::DecodeOptions::new()
+        .read_info(Cursor::new(&gif_data))
+        .unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (3, 2));
+    assert_eq!(decoder.read_next_frame().unwrap().unwrap().delay, 10);
+    let frame = decoder.read_next_frame().unwrap().unwrap();
+    assert_eq!(
+        (frame.delay, frame.buffer.as_ref()),
+        (5, &[0, 0, 0, 0, 1, 0][..])
+    );
+    assert!(decoder.read_next_frame().unwrap().is_none());
+
+    let mut png_data = Vec::new();
+    write_animation_to_png(&frames, 50, &mut png_data).unwrap();
+    let reader = Decoder::new(Cursor::new(&png_data)).read_info().unwrap();
+    assert_eq!(reader.info().animation_control.unwrap().num_frames, 2);
+    assert_eq!(
+        read_apng_frames(Cursor::new(&png_data), |frame| Ok(frame.clone())).unwrap()[1]
+            .to_gray_with(GrayChannel::Rec709)
+            .pixels,
+        vec![0, 0, 0, 0, 0xFF, 0]
+    );
+}
+
+/// Rectangle of the source image to import
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ImageRegion {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl fmt::Display for ImageRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+impl FromStr for ImageRegion {
+    type Err = ();
+
+    /// Parse `x,y,width,height`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let values = value
+            .split(',')
+            .map(|v| usize::from_str(v.trim()).map_err(|_| ()))
+            .collect::<Result<Vec<_>, _>>()?;
+        match values[..] {
+            [x, y, width, height] if width > 0 && height > 0 => Ok(ImageRegion {
+                x,
+                y,
+                width,
+                height,
+            }),
+            _ => Err(()),
+        }
     }
+}
+
+#[test]
+fn test_image_region_from_str() {
+    assert_eq!(
+        ImageRegion::from_str("1, 2,3,4"),
+        Ok(ImageRegion {
+            x: 1,
+            y: 2,
+            width: 3,
+            height: 4
+        })
+    );
+    assert_eq!(
+        ImageRegion::from_str("1,2,3,4").unwrap().to_string(),
+        "1,2,3,4"
+    );
+    assert!(ImageRegion::from_str("1,2,0,4").is_err());
+    assert!(ImageRegion::from_str("1,2,3").is_err());
+    assert!(ImageRegion::from_str("1,2,3,x").is_err());
+}
+
+/// Grid of the equally sized cells of a sprite sheet
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ImageGrid {
+    pub columns: usize,
+    pub rows: usize,
+}
 
-    let byte_per_pixel = match info.color_type {
-        ColorType::Grayscale => 1,
-        ColorType::Rgb => 3,
-        ColorType::Indexed => 3,
-        ColorType::GrayscaleAlpha => 2,
-        ColorType::Rgba => 4,
+impl FromStr for ImageGrid {
+    type Err = ();
+
+    /// Parse `columns`x`rows`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (columns, rows) = value.split_once(['x', 'X']).ok_or(())?;
+        match (usize::from_str(columns), usize::from_str(rows)) {
+            (Ok(columns), Ok(rows)) if columns > 0 && rows > 0 => Ok(ImageGrid { columns, rows }),
+            _ => Err(()),
+        }
+    }
+}
+
+#[test]
+fn test_image_grid_from_str() {
+    assert_eq!(
+        ImageGrid::from_str("4x2"),
+        Ok(ImageGrid {
+            columns: 4,
+            rows: 2
+        })
+    );
+    assert!(ImageGrid::from_str("4x0").is_err());
+    assert!(ImageGrid::from_str("4").is_err());
+}
+
+/// Options to convert the images into the message bitmap
+#[derive(Debug, Clone)]
+pub struct ImageImportOptions {
+    /// Color under the transparent pixels
+    pub background: Color,
+    /// Width to fit the image in; the image is scaled to the message height if `None`.
+    pub max_width: Option<usize>,
+    /// Method to convert into the monochrome bitmap
+    pub dither: DitherMethod,
+    /// Gray level from which the pixels are lit, unless chosen by the dithering method
+    pub threshold: u8,
+    /// Light up the dark pixels instead of the bright ones
+    pub invert: bool,
+    /// Light up the opaque pixels regardless of their color
+    pub alpha_mask: bool,
+    /// Channel or luminance formula to convert the colors into the gray levels
+    pub channel: GrayChannel,
+    /// Part of the image to import; the whole image if `None`.
+    pub region: Option<ImageRegion>,
+    /// Round the stroke widths of svg to whole pixels
+    pub snap_strokes: bool,
+    /// Antialias the edges of svg shapes
+    pub antialias: bool,
+}
+
+impl Default for ImageImportOptions {
+    fn default() -> Self {
+        ImageImportOptions {
+            background: Color::new(0x00, 0x00, 0x00),
+            max_width: None,
+            dither: DitherMethod::Threshold,
+            threshold: 0x80,
+            invert: false,
+            alpha_mask: false,
+            channel: GrayChannel::Rec709,
+            region: None,
+            snap_strokes: false,
+            antialias: true,
+        }
+    }
+}
+
+/// Scale the image to the message height and convert into the monochrome bitmap
+fn image_to_canvas(image: &RgbaImage, options: &ImageImportOptions) -> Canvas {
+    let gray = if options.alpha_mask {
+        image.alpha()
+    } else {
+        image
+            .composite(options.background)
+            .to_gray_with(options.channel)
+    };
+    let gray = if options.invert { gray.invert() } else { gray };
+    gray.fit(BADGE_MSG_FONT_HEIGHT, options.max_width)
+        .dither(options.dither, options.threshold)
+}
+
+#[test]
+fn test_image_to_canvas_options() {
+    let image = RgbaImage {
+        width: 4,
+        height: BADGE_MSG_FONT_HEIGHT,
+        pixels: [
+            [0xFF, 0x00, 0x00, 0xFF],
+            [0x60, 0x60, 0x60, 0xFF],
+            [0x00, 0x00, 0x00, 0x80],
+            [0xFF, 0xFF, 0xFF, 0x00],
+        ]
+        .repeat(BADGE_MSG_FONT_HEIGHT),
+    };
+    let lit = |options: ImageImportOptions| {
+        let canvas = image_to_canvas(&image, &options);
+        (0..4).map(|x| canvas.get(x, 0)).collect::<Vec<_>>()
     };
-    let mut buf = vec![0; reader.output_buffer_size().unwrap()];
-    reader.next_frame(&mut buf)?;
-    let mut data = vec![0; (info.width as usize).div_ceil(8) * BADGE_MSG_FONT_HEIGHT];
-    for (i, &v) in buf.iter().step_by(byte_per_pixel).enumerate() {
-        let canvas_x = i % info.width as usize;
-        let canvas_y = i / info.width as usize;
-        let data_x = canvas_x / 8;
-        let data_offset = canvas_x % 8;
-        let data_y = canvas_y;
-        let data_index = data_x * BADGE_MSG_FONT_HEIGHT + data_y;
 
-        if v >= 0x80 {
-            data[data_index] |= 0x80u8 >> data_offset as u8;
+    assert_eq!(lit(Default::default()), [false, false, false, false]);
+    assert_eq!(
+        lit(ImageImportOptions {
+            threshold: 0x30,
+            ..Default::default()
+        }),
+        [true, true, false, false]
+    );
+    assert_eq!(
+        lit(ImageImportOptions {
+            channel: GrayChannel::Red,
+            ..Default::default()
+        }),
+        [true, false, false, false]
+    );
+    assert_eq!(
+        lit(ImageImportOptions {
+            invert: true,
+            ..Default::default()
+        }),
+        [true, true, true, true]
+    );
+    assert_eq!(
+        lit(ImageImportOptions {
+            alpha_mask: true,
+            ..Default::default()
+        }),
+        [true, true, true, false]
+    );
+    assert_eq!(
+        lit(ImageImportOptions {
+            alpha_mask: true,
+            invert: true,
+            ..Default::default()
+        }),
+        [false, false, false, true]
+    );
+}
+
+/// Crop the image to the region of the options
+fn crop_to_region(
+    image: RgbaImage,
+    options: &ImageImportOptions,
+) -> Result<RgbaImage, BadgeImageReadError> {
+    match options.region {
+        None => Ok(image),
+        Some(region)
+            if region
+                .x
+                .checked_add(region.width)
+                .is_some_and(|right| right <= image.width)
+                && region
+                    .y
+                    .checked_add(region.height)
+                    .is_some_and(|bottom| bottom <= image.height) =>
+        {
+            Ok(image.crop(region.x, region.y, region.width, region.height))
         }
+        Some(region) => Err(BadgeImageReadError::InvalidImageData(format!(
+            "region {} is out of the {}x{} image",
+            region, image.width, image.height
+        ))),
     }
+}
 
-    Ok(data.to_owned())
+pub fn read_png_to_canvas<R: Read + BufRead + Seek>(
+    reader: R,
+    options: &ImageImportOptions,
+) -> Result<Canvas, BadgeImageReadError> {
+    read_png(reader, options).map(|(canvas, _)| canvas)
+}
+
+/// Text chunks of the png image as (keyword, text)
+pub type PngTextChunks = Vec<(String, String)>;
+
+fn png_text_chunks(info: &Info) -> PngTextChunks {
+    let latin1_text = info
+        .uncompressed_latin1_text
+        .iter()
+        .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()));
+    let compressed_text = info
+        .compressed_latin1_text
+        .iter()
+        .filter_map(|chunk| Some((chunk.keyword.clone(), chunk.get_text().ok()?)));
+    let utf8_text = info
+        .utf8_text
+        .iter()
+        .filter_map(|chunk| Some((chunk.keyword.clone(), chunk.get_text().ok()?)));
+    latin1_text
+        .chain(compressed_text)
+        .chain(utf8_text)
+        .collect()
+}
+
+/// Decode the png image with its text chunks
+fn read_png_image<R: Read + BufRead + Seek>(
+    reader: R,
+) -> Result<(RgbaImage, PngTextChunks), BadgeImageReadError> {
+    let mut decoder = Decoder::new(reader);
+    // any bit depth, palette and transparency into 8-bit gray/rgb with or without alpha
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let buffer_size = reader
+        .output_buffer_size()
+        .ok_or_else(|| BadgeImageReadError::InvalidImageData("png: too large image".to_string()))?;
+    let mut buf = vec![0; buffer_size];
+    let output = reader.next_frame(&mut buf)?;
+
+    Ok((
+        png_output_to_rgba(&buf, &output),
+        png_text_chunks(reader.info()),
+    ))
+}
+
+/// Read the png image and its text chunks
+fn read_png<R: Read + BufRead + Seek>(
+    reader: R,
+    options: &ImageImportOptions,
+) -> Result<(Canvas, PngTextChunks), BadgeImageReadError> {
+    let (image, text_chunks) = read_png_image(reader)?;
+    let canvas = image_to_canvas(&crop_to_region(image, options)?, options);
+
+    Ok((canvas, text_chunks))
+}
+
+/// Read the png image as the message data with the text chunks of (keyword, text)
+pub fn read_png_to_badge_message<R: Read + BufRead + Seek>(
+    reader: R,
+    options: &ImageImportOptions,
+) -> Result<(Vec<u8>, PngTextChunks), BadgeImageReadError> {
+    read_png(reader, options).map(|(canvas, text_chunks)| (canvas2vec(&canvas), text_chunks))
+}
+
+#[test]
+fn test_png_text_chunks() {
+    let message_data = vec![0x80; BADGE_MSG_FONT_HEIGHT];
+    let text_chunks = [
+        ("key", "value".to_string()),
+        ("text", "ÄÖÜ 漢字".to_string()),
+    ];
+    let mut png_data = Vec::new();
+    write_badge_message_to_png(&message_data, &text_chunks, &mut png_data).unwrap();
+    let (data, text_chunks) =
+        read_png_to_badge_message(Cursor::new(&png_data), &ImageImportOptions::default()).unwrap();
+    assert_eq!(data, message_data);
+    assert_eq!(
+        text_chunks,
+        vec![
+            ("key".to_string(), "value".to_string()),
+            ("text".to_string(), "ÄÖÜ 漢字".to_string())
+        ]
+    );
 }
 
 #[test]
@@ -194,7 +625,10 @@ fn test_read_png_to_badge_message() {
     let png_data = create_png_data(16, ColorType::Grayscale, BitDepth::Eight, &sample_pixels);
     let r = Cursor::new(&png_data);
     assert_eq!(
-        read_png_to_badge_message(r).unwrap().as_slice(),
+        read_png_to_badge_message(r, &ImageImportOptions::default())
+            .unwrap()
+            .0
+            .as_slice(),
         &sample_data
     );
 
@@ -211,7 +645,10 @@ fn test_read_png_to_badge_message() {
     );
     let r = Cursor::new(&png_data);
     assert_eq!(
-        read_png_to_badge_message(r).unwrap().as_slice(),
+        read_png_to_badge_message(r, &ImageImportOptions::default())
+            .unwrap()
+            .0
+            .as_slice(),
         &sample_data
     );
 
@@ -223,7 +660,10 @@ fn test_read_png_to_badge_message() {
     let png_data = create_png_data(16, ColorType::Rgb, BitDepth::Eight, &sample_pixels_rgb);
     let r = Cursor::new(&png_data);
     assert_eq!(
-        read_png_to_badge_message(r).unwrap().as_slice(),
+        read_png_to_badge_message(r, &ImageImportOptions::default())
+            .unwrap()
+            .0
+            .as_slice(),
         &sample_data
     );
 
@@ -235,7 +675,1572 @@ fn test_read_png_to_badge_message() {
     let png_data = create_png_data(16, ColorType::Rgba, BitDepth::Eight, &sample_pixels_rgba);
     let r = Cursor::new(&png_data);
     assert_eq!(
-        read_png_to_badge_message(r).unwrap().as_slice(),
+        read_png_to_badge_message(r, &ImageImportOptions::default())
+            .unwrap()
+            .0
+            .as_slice(),
         &sample_data
     );
 }
+
+#[test]
+fn test_read_png_to_canvas() {
+    let mut png_data = Vec::new();
+    {
+        let w = Cursor::new(&mut png_data);
+        let mut encoder = Encoder::new(w, 5, BADGE_MSG_FONT_HEIGHT as u32);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        let mut pixels = vec![0u8; 5 * BADGE_MSG_FONT_HEIGHT];
+        pixels[4] = 0xFF;
+        writer.write_image_data(&pixels).unwrap();
+    }
+
+    let canvas =
+        read_png_to_canvas(Cursor::new(&png_data), &ImageImportOptions::default()).unwrap();
+    assert_eq!((canvas.width, canvas.height), (5, BADGE_MSG_FONT_HEIGHT));
+    assert_eq!(canvas.content_columns(), Some(4..5));
+}
+
+#[test]
+fn test_read_png_to_canvas_scaling() {
+    // 8x22 image whose left half is lit
+    let mut png_data = Vec::new();
+    let mut encoder = Encoder::new(&mut png_data, 8, 2 * BADGE_MSG_FONT_HEIGHT as u32);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer
+        .write_image_data(&[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0].repeat(2 * BADGE_MSG_FONT_HEIGHT))
+        .unwrap();
+    writer.finish().unwrap();
+
+    let canvas =
+        read_png_to_canvas(Cursor::new(&png_data), &ImageImportOptions::default()).unwrap();
+    assert_eq!((canvas.width, canvas.height), (4, BADGE_MSG_FONT_HEIGHT));
+    assert_eq!(canvas.content_columns(), Some(0..2));
+
+    let options = ImageImportOptions {
+        max_width: Some(2),
+        dither: DitherMethod::Otsu,
+        ..Default::default()
+    };
+    let canvas = read_png_to_canvas(Cursor::new(&png_data), &options).unwrap();
+    assert_eq!((canvas.width, canvas.height), (2, BADGE_MSG_FONT_HEIGHT));
+    assert!(canvas.get(0, 5) && !canvas.get(1, 5));
+    assert!(!canvas.get(0, 0));
+}
+
+#[test]
+fn test_read_png_to_canvas_formats() {
+    fn create_png_data(
+        color_type: ColorType,
+        bit_depth: BitDepth,
+        palette: Option<Vec<u8>>,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut png_data = Vec::new();
+        let mut encoder = Encoder::new(&mut png_data, 8, BADGE_MSG_FONT_HEIGHT as u32);
+        encoder.set_color(color_type);
+        encoder.set_depth(bit_depth);
+        if let Some(palette) = palette {
+            encoder.set_palette(palette);
+        }
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        writer.finish().unwrap();
+        png_data
+    }
+    let options = ImageImportOptions::default();
+    let read = |png_data: &[u8], options: &ImageImportOptions| {
+        canvas2vec(&read_png_to_canvas(Cursor::new(png_data), options).unwrap())
+    };
+    let expected = vec![0b1000_0001; BADGE_MSG_FONT_HEIGHT];
+
+    // 1-bit monochrome
+    let png_data = create_png_data(
+        ColorType::Grayscale,
+        BitDepth::One,
+        None,
+        &[0b1000_0001; BADGE_MSG_FONT_HEIGHT],
+    );
+    assert_eq!(read(&png_data, &options), expected);
+
+    // 4-bit palette of dark blue and bright green
+    let png_data = create_png_data(
+        ColorType::Indexed,
+        BitDepth::Four,
+        Some(vec![0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00]),
+        &[0x10, 0x00, 0x00, 0x01].repeat(BADGE_MSG_FONT_HEIGHT),
+    );
+    assert_eq!(read(&png_data, &options), expected);
+
+    // 16-bit rgb; only the green channel is bright enough
+    let mut pixels = [[0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00]; 8];
+    pixels[0] = [0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00];
+    pixels[7] = [0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00];
+    let png_data = create_png_data(
+        ColorType::Rgb,
+        BitDepth::Sixteen,
+        None,
+        &pixels.as_flattened().repeat(BADGE_MSG_FONT_HEIGHT),
+    );
+    assert_eq!(read(&png_data, &options), expected);
+
+    // transparent pixels get the background color
+    let png_data = create_png_data(
+        ColorType::GrayscaleAlpha,
+        BitDepth::Eight,
+        None,
+        &[[0xFF, 0xFF], [0x00, 0xFF], [0x00, 0x00], [0x00, 0x00]]
+            .as_flattened()
+            .repeat(2 * BADGE_MSG_FONT_HEIGHT),
+    );
+    assert_eq!(
+        read(&png_data, &options),
+        vec![0b1000_1000; BADGE_MSG_FONT_HEIGHT]
+    );
+    let options = ImageImportOptions {
+        background: Color::new(0xFF, 0xFF, 0xFF),
+        ..Default::default()
+    };
+    assert_eq!(
+        read(&png_data, &options),
+        vec![0b1011_1011; BADGE_MSG_FONT_HEIGHT]
+    );
+}
+
+/// Operation applied to the animation screen after the frame is shown
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum FrameDispose {
+    None,
+    Background,
+    Previous,
+}
+
+/// Partial image drawn on the animation screen
+struct SubFrame {
+    image: RgbaImage,
+    x: usize,
+    y: usize,
+    blend_over: bool,
+    dispose: FrameDispose,
+}
+
+/// Screen of the animation on which the sub-frames are drawn in order
+struct AnimationScreen {
+    image: RgbaImage,
+    /// disposal of the sub-frame drawn last with its area on the screen
+    dispose: Option<(FrameDispose, ImageRegion)>,
+    /// screen before the sub-frame drawn last, kept only to be restored by its disposal
+    previous: Option<RgbaImage>,
+}
+
+impl AnimationScreen {
+    /// Create the blank screen; the too large screen is rejected before allocated.
+    fn new(width: usize, height: usize) -> Result<Self, BadgeImageReadError> {
+        if width
+            .checked_mul(height)
+            .is_none_or(|pixels| pixels > MAX_IMAGE_PIXELS)
+        {
+            return Err(BadgeImageReadError::InvalidImageData(format!(
+                "screen {}x{} is too large",
+                width, height
+            )));
+        }
+        Ok(AnimationScreen {
+            image: RgbaImage::new(width, height),
+            dispose: None,
+            previous: None,
+        })
+    }
+
+    /// Dispose the sub-frame drawn last, then draw the sub-frame and return the screen image.
+    fn draw(&mut self, sub_frame: &SubFrame) -> &RgbaImage {
+        let (width, height) = (self.image.width, self.image.height);
+        match self.dispose.take() {
+            Some((FrameDispose::Background, area)) => {
+                for q in area.y..area.y + area.height {
+                    self.image.pixels[q * width + area.x..][..area.width].fill([0; 4]);
+                }
+            }
+            Some((FrameDispose::Previous, _)) => {
+                if let Some(previous) = self.previous.take() {
+                    self.image = previous;
+                }
+            }
+            _ => (),
+        }
+        if sub_frame.dispose == FrameDispose::Previous {
+            self.previous = Some(self.image.clone());
+        }
+
+        let (x, y) = (sub_frame.x.min(width), sub_frame.y.min(height));
+        let area = ImageRegion {
+            x,
+            y,
+            width: sub_frame.image.width.min(width - x),
+            height: sub_frame.image.height.min(height - y),
+        };
+        for q in 0..area.height {
+            for p in 0..area.width {
+                let src = sub_frame.image.pixels[q * sub_frame.image.width + p];
+                let dst = &mut self.image.pixels[(y + q) * width + x + p];
+                *dst = if sub_frame.blend_over {
+                    blend_over(src, *dst)
+                } else {
+                    src
+                };
+            }
+        }
+        self.dispose = Some((sub_frame.dispose, area));
+        &self.image
+    }
+}
+
+/// Composite the source pixel over the destination pixel
+fn blend_over(src: [u8; 4], dst: [u8; 4]) -> [u8; 4] {
+    let src_alpha = src[3] as f32 / 255.0;
+    let dst_alpha = dst[3] as f32 / 255.0 * (1.0 - src_alpha);
+    let alpha = src_alpha + dst_alpha;
+    if alpha > 0.0 {
+        let mut blended = [0, 0, 0, (alpha * 255.0).round() as u8];
+        for c in 0..3 {
+            blended[c] =
+                ((src[c] as f32 * src_alpha + dst[c] as f32 * dst_alpha) / alpha).round() as u8;
+        }
+        blended
+    } else {
+        [0; 4]
+    }
+}
+
+#[test]
+fn test_animation_screen() {
+    let white = [0xFF; 4];
+    let sub_image = RgbaImage {
+        width: 1,
+        height: 1,
+        pixels: vec![white],
+    };
+    let sub_frames = [
+        SubFrame {
+            image: sub_image.clone(),
+            x: 0,
+            y: 0,
+            blend_over: true,
+            dispose: FrameDispose::None,
+        },
+        SubFrame {
+            image: sub_image.clone(),
+            x: 1,
+            y: 0,
+            blend_over: true,
+            dispose: FrameDispose::Previous,
+        },
+        SubFrame {
+            image: sub_image.clone(),
+            x: 2,
+            y: 0,
+            blend_over: false,
+            dispose: FrameDispose::Background,
+        },
+        SubFrame {
+            image: RgbaImage::new(1, 1),
+            x: 0,
+            y: 0,
+            blend_over: true,
+            dispose: FrameDispose::None,
+        },
+        // out of the screen
+        SubFrame {
+            image: sub_image.clone(),
+            x: 3,
+            y: 1,
+            blend_over: true,
+            dispose: FrameDispose::Background,
+        },
+    ];
+    let mut screen = AnimationScreen::new(3, 1).unwrap();
+    let frames = sub_frames
+        .iter()
+        .map(|sub_frame| screen.draw(sub_frame).pixels.clone())
+        .collect::<Vec<_>>();
+    let blank = [0; 4];
+    assert_eq!(frames[0], vec![white, blank, blank]);
+    assert_eq!(frames[1], vec![white, white, blank]);
+    assert_eq!(frames[2], vec![white, blank, white]);
+    assert_eq!(frames[3], vec![white, blank, blank]);
+    assert_eq!(frames[4], vec![white, blank, blank]);
+
+    assert!(matches!(
+        AnimationScreen::new(65535, 65535),
+        Err(BadgeImageReadError::InvalidImageData(_))
+    ));
+    assert!(AnimationScreen::new(usize::MAX, 2).is_err());
+}
+
+/// Convert the decoded png frame into the RGBA image.
+///
+/// The frame must be decoded with `Transformations::EXPAND` and `Transformations::STRIP_16`.
+fn png_output_to_rgba(buf: &[u8], output: &OutputInfo) -> RgbaImage {
+    let (width, height) = (output.width as usize, output.height as usize);
+    let samples = output.color_type.samples();
+    let mut image = RgbaImage::new(width, height);
+    for y in 0..height {
+        let line = &buf[y * output.line_size..];
+        for x in 0..width {
+            let v = &line[x * samples..(x + 1) * samples];
+            image.pixels[y * width + x] = match output.color_type {
+                ColorType::Grayscale => [v[0], v[0], v[0], 0xFF],
+                ColorType::GrayscaleAlpha => [v[0], v[0], v[0], v[1]],
+                ColorType::Rgb | ColorType::Indexed => [v[0], v[1], v[2], 0xFF],
+                ColorType::Rgba => [v[0], v[1], v[2], v[3]],
+            };
+        }
+    }
+    image
+}
+
+/// Read all frames of the animated png converted by `convert`; a still png gives a single frame.
+fn read_apng_frames<R: BufRead + Seek, T>(
+    reader: R,
+    mut convert: impl FnMut(&RgbaImage) -> Result<T, BadgeImageReadError>,
+) -> Result<Vec<T>, BadgeImageReadError> {
+    let mut decoder = Decoder::new(reader);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let (width, height) = (reader.info().width as usize, reader.info().height as usize);
+    let invalid =
+        |message: &str| BadgeImageReadError::InvalidImageData(format!("apng: {}", message));
+    let buffer_size = reader
+        .output_buffer_size()
+        .ok_or_else(|| invalid("too large image"))?;
+    let mut buf = vec![0; buffer_size];
+
+    let Some(num_frames) = reader.info().animation_control.map(|v| v.num_frames) else {
+        let output = reader.next_frame(&mut buf)?;
+        return Ok(vec![convert(&png_output_to_rgba(&buf, &output))?]);
+    };
+    let mut screen = AnimationScreen::new(width, height)?;
+    if reader.info().frame_control.is_none() {
+        // the default image is not a part of the animation
+        reader.next_frame(&mut buf)?;
+    }
+
+    // the frames are not allocated ahead since the number of frames is not trusted
+    let mut frames = Vec::new();
+    for i in 0..num_frames {
+        let output = reader.next_frame(&mut buf)?;
+        let frame_control = reader
+            .info()
+            .frame_control
+            .ok_or_else(|| invalid("frame control not found"))?;
+        let sub_frame = SubFrame {
+            image: png_output_to_rgba(&buf, &output),
+            x: frame_control.x_offset as usize,
+            y: frame_control.y_offset as usize,
+            blend_over: frame_control.blend_op == BlendOp::Over,
+            dispose: match frame_control.dispose_op {
+                DisposeOp::None => FrameDispose::None,
+                DisposeOp::Previous if i > 0 => FrameDispose::Previous,
+                DisposeOp::Background | DisposeOp::Previous => FrameDispose::Background,
+            },
+        };
+        frames.push(convert(screen.draw(&sub_frame))?);
+    }
+    Ok(frames)
+}
+
+/// Start decoding the gif image and create its blank screen
+fn read_gif_info<R: Read>(
+    reader: R,
+) -> Result<(gif::Decoder<R>, AnimationScreen), BadgeImageReadError> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    // the size of each frame is limited by the memory limit of the decoder
+    let decoder = options.read_info(reader)?;
+    let screen = AnimationScreen::new(decoder.width() as usize, decoder.height() as usize)?;
+    Ok((decoder, screen))
+}
+
+/// Sub-frame of the decoded gif frame
+fn gif_sub_frame(frame: &gif::Frame) -> SubFrame {
+    SubFrame {
+        image: RgbaImage {
+            width: frame.width as usize,
+            height: frame.height as usize,
+            pixels: frame
+                .buffer
+                .chunks_exact(4)
+                .map(|v| [v[0], v[1], v[2], v[3]])
+                .collect(),
+        },
+        x: frame.left as usize,
+        y: frame.top as usize,
+        blend_over: true,
+        dispose: match frame.dispose {
+            gif::DisposalMethod::Any | gif::DisposalMethod::Keep => FrameDispose::None,
+            gif::DisposalMethod::Background => FrameDispose::Background,
+            gif::DisposalMethod::Previous => FrameDispose::Previous,
+        },
+    }
+}
+
+/// Read all frames of the gif image converted by `convert`
+fn read_gif_frames<R: Read, T>(
+    reader: R,
+    mut convert: impl FnMut(&RgbaImage) -> Result<T, BadgeImageReadError>,
+) -> Result<Vec<T>, BadgeImageReadError> {
+    let (mut decoder, mut screen) = read_gif_info(reader)?;
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame()? {
+        frames.push(convert(screen.draw(&gif_sub_frame(frame)))?);
+    }
+    Ok(frames)
+}
+
+#[test]
+fn test_read_gif_frames() {
+    // the screen of 65535x65535 pixels is rejected before allocated
+    let mut gif_data = Vec::new();
+    {
+        let palette = [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF];
+        let mut encoder = gif::Encoder::new(&mut gif_data, 65535, 65535, &palette).unwrap();
+        let frame = gif::Frame {
+            width: 1,
+            height: 1,
+            buffer: std::borrow::Cow::Borrowed(&[1]),
+            ..Default::default()
+        };
+        encoder.write_frame(&frame).unwrap();
+    }
+    assert!(matches!(
+        read_gif_frames(Cursor::new(&gif_data), |_| Ok(())),
+        Err(BadgeImageReadError::InvalidImageData(_))
+    ));
+}
+
+/// Read the first frame of the gif image
+fn read_gif_image<R: Read>(reader: R) -> Result<RgbaImage, BadgeImageReadError> {
+    let (mut decoder, mut screen) = read_gif_info(reader)?;
+    let frame = decoder
+        .read_next_frame()?
+        .ok_or_else(|| BadgeImageReadError::InvalidImageData("gif: frame not found".to_string()))?;
+    screen.draw(&gif_sub_frame(frame));
+    Ok(screen.image)
+}
+
+#[test]
+fn test_read_gif_image() {
+    // 2-frame gif animation of 2x1 pixels; the first pixel then the second one is lit
+    let mut gif_data = Vec::new();
+    {
+        let palette = [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF];
+        let mut encoder = gif::Encoder::new(&mut gif_data, 2, 1, &palette).unwrap();
+        for pixels in [[1, 0], [0, 1]] {
+            let frame = gif::Frame {
+                width: 2,
+                height: 1,
+                buffer: std::borrow::Cow::Borrowed(&pixels),
+                ..Default::default()
+            };
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+    let image = read_gif_image(Cursor::new(&gif_data)).unwrap();
+    assert_eq!(image.pixels, vec![[0xFF; 4], [0, 0, 0, 0xFF]]);
+
+    // the screen of 65535x65535 pixels is rejected as well as the animation
+    let mut gif_data = Vec::new();
+    {
+        let palette = [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF];
+        let mut encoder = gif::Encoder::new(&mut gif_data, 65535, 65535, &palette).unwrap();
+        let frame = gif::Frame {
+            width: 1,
+            height: 1,
+            buffer: std::borrow::Cow::Borrowed(&[1]),
+            ..Default::default()
+        };
+        encoder.write_frame(&frame).unwrap();
+    }
+    assert!(matches!(
+        read_gif_image(Cursor::new(&gif_data)),
+        Err(BadgeImageReadError::InvalidImageData(_))
+    ));
+
+    // no frame
+    let mut gif_data = Vec::new();
+    gif::Encoder::new(&mut gif_data, 1, 1, &[0; 6]).unwrap();
+    assert!(read_gif_image(Cursor::new(&gif_data)).is_err());
+}
+
+/// Read the animated gif or png and convert each frame to fit in the `frame_width` columns.
+///
+/// Each frame is converted in the same way as the still image with the `options`,
+/// except that the width is limited to `frame_width` as well.
+pub fn read_animation_to_canvases<R: Read + BufRead + Seek>(
+    mut reader: R,
+    frame_width: usize,
+    options: &ImageImportOptions,
+) -> Result<Vec<Canvas>, BadgeImageReadError> {
+    let frame_options = ImageImportOptions {
+        max_width: Some(
+            options
+                .max_width
+                .map_or(frame_width, |w| w.min(frame_width)),
+        ),
+        ..options.clone()
+    };
+    let convert = |frame: &RgbaImage| {
+        let fitted = image_to_canvas(
+            &crop_to_region(frame.clone(), &frame_options)?,
+            &frame_options,
+        );
+        let mut canvas = Canvas::new(frame_width, BADGE_MSG_FONT_HEIGHT);
+        canvas.blit_columns(&fitted, 0..fitted.width, (frame_width - fitted.width) / 2);
+        Ok(canvas)
+    };
+
+    let signature = reader.fill_buf()?;
+    if signature.starts_with(PNG_SIGNATURE) {
+        read_apng_frames(reader, convert)
+    } else if signature.starts_with(GIF_SIGNATURE) {
+        read_gif_frames(reader, convert)
+    } else {
+        Err(BadgeImageReadError::UnknownImageFormat)
+    }
+}
+
+#[test]
+fn test_read_animation_to_canvases() {
+    // 2-frame gif animation of 2x1 pixels; the first pixel then the second one is lit
+    let mut gif_data = Vec::new();
+    {
+        let palette = [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF];
+        let mut encoder = gif::Encoder::new(&mut gif_data, 2, 1, &palette).unwrap();
+        for pixels in [[1, 0], [0, 1]] {
+            let frame = gif::Frame {
+                width: 2,
+                height: 1,
+                buffer: std::borrow::Cow::Borrowed(&pixels),
+                ..Default::default()
+            };
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+    let canvases =
+        read_animation_to_canvases(Cursor::new(&gif_data), 22, &Default::default()).unwrap();
+    assert_eq!(canvases.len(), 2);
+    assert_eq!((canvases[0].width, canvases[0].height), (22, 11));
+    assert_eq!(canvases[0].content_columns(), Some(0..11));
+    assert_eq!(canvases[1].content_columns(), Some(11..22));
+
+    // same animation in apng
+    let mut png_data = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut png_data, 2, 1);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.set_animated(2, 0).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0xFF, 0x00]).unwrap();
+        writer.write_image_data(&[0x00, 0xFF]).unwrap();
+    }
+    let apng_canvases =
+        read_animation_to_canvases(Cursor::new(&png_data), 22, &Default::default()).unwrap();
+    assert_eq!(apng_canvases, canvases);
+
+    // the import options are applied to each frame
+    let inverted = read_animation_to_canvases(
+        Cursor::new(&gif_data),
+        22,
+        &ImageImportOptions {
+            invert: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(inverted[0].content_columns(), Some(11..22));
+    assert_eq!(inverted[1].content_columns(), Some(0..11));
+    let narrowed = read_animation_to_canvases(
+        Cursor::new(&gif_data),
+        22,
+        &ImageImportOptions {
+            max_width: Some(2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(narrowed[0].content_columns(), Some(10..11));
+    assert!(
+        read_animation_to_canvases(
+            Cursor::new(&gif_data),
+            22,
+            &ImageImportOptions {
+                region: Some(ImageRegion {
+                    x: 1,
+                    y: 0,
+                    width: 2,
+                    height: 1,
+                }),
+                ..Default::default()
+            },
+        )
+        .is_err()
+    );
+
+    // the number of frames in acTL is not trusted
+    let mut png_data = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut png_data, 2, 1);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.set_animated(0x0FFF_FFFF, 0).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0xFF, 0x00]).unwrap();
+    }
+    assert!(read_animation_to_canvases(Cursor::new(&png_data), 22, &Default::default()).is_err());
+
+    assert!(matches!(
+        read_animation_to_canvases(Cursor::new(&[0u8; 16]), 22, &Default::default()),
+        Err(BadgeImageReadError::UnknownImageFormat)
+    ));
+}
+
+/// Split the netpbm header into tokens, skipping the comments.
+///
+/// Returns the tokens and the offset just after the single whitespace ending the header.
+fn netpbm_header(data: &[u8], count: usize) -> Result<(Vec<usize>, usize), BadgeImageReadError> {
+    let invalid = || BadgeImageReadError::InvalidImageData("netpbm: broken header".to_string());
+    let mut tokens = Vec::with_capacity(count);
+    let mut i = 2; // after the magic number
+    while tokens.len() < count {
+        match data.get(i).ok_or_else(invalid)? {
+            b'#' => {
+                while data.get(i).is_some_and(|&c| c != b'\n') {
+                    i += 1;
+                }
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while data.get(i).is_some_and(|c| c.is_ascii_digit()) {
+                    i += 1;
+                }
+                let token = std::str::from_utf8(&data[start..i]).unwrap();
+                tokens.push(token.parse().map_err(|_| invalid())?);
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok((tokens, i + 1))
+}
+
+/// Read PBM (P1, P4) and PGM (P2, P5) image.
+///
+/// In PBM, the set bits (black) are read as white, i.e. the lit LEDs.
+fn read_netpbm(data: &[u8]) -> Result<RgbaImage, BadgeImageReadError> {
+    let magic = &data[0..2];
+    let is_bitmap = magic == b"P1" || magic == b"P4";
+    let (header, offset) = netpbm_header(data, if is_bitmap { 2 } else { 3 })?;
+    let (width, height) = (header[0], header[1]);
+    let max_value = if is_bitmap { 1 } else { header[2].max(1) };
+    let body = data.get(offset..).unwrap_or_default();
+    let truncated = || BadgeImageReadError::InvalidImageData("netpbm: truncated data".to_string());
+    if max_value > 0xFFFF {
+        return Err(BadgeImageReadError::InvalidImageData(
+            "netpbm: wrong maximum value".to_string(),
+        ));
+    }
+
+    // the data has at least a byte for each pixel, or for each 8 pixels of a P4 row
+    let pixel_count = width.checked_mul(height).ok_or_else(truncated)?;
+    let required = match magic {
+        b"P4" => width.div_ceil(8).checked_mul(height),
+        _ if max_value > 0xFF => pixel_count.checked_mul(2),
+        _ => Some(pixel_count),
+    };
+    if required.is_none_or(|required| body.len() < required) {
+        return Err(truncated());
+    }
+
+    let values: Vec<usize> = match magic {
+        b"P1" => body
+            .iter()
+            .filter(|c| matches!(c, b'0' | b'1'))
+            .map(|&c| (c == b'1') as usize)
+            .take(pixel_count)
+            .collect(),
+        b"P2" => std::str::from_utf8(body)
+            .map_err(|_| truncated())?
+            .split_ascii_whitespace()
+            .take(pixel_count)
+            .map(|v| v.parse().map_err(|_| truncated()))
+            .collect::<Result<_, _>>()?,
+        b"P4" => {
+            let stride = width.div_ceil(8);
+            (0..pixel_count)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    body.get(y * stride + x / 8)
+                        .map(|&v| (v >> (7 - x % 8)) as usize & 1)
+                })
+                .collect::<Option<_>>()
+                .ok_or_else(truncated)?
+        }
+        _ if max_value > 0xFF => body
+            .chunks_exact(2)
+            .map(|v| u16::from_be_bytes([v[0], v[1]]) as usize)
+            .take(pixel_count)
+            .collect(),
+        _ => body.iter().map(|&v| v as usize).take(pixel_count).collect(),
+    };
+    if values.len() < pixel_count {
+        return Err(truncated());
+    }
+
+    let mut image = RgbaImage::new(width, height);
+    for (pixel, v) in image.pixels.iter_mut().zip(values) {
+        let v = (v.min(max_value) * 0xFF / max_value) as u8;
+        *pixel = [v, v, v, 0xFF];
+    }
+    Ok(image)
+}
+
+#[test]
+fn test_read_netpbm() {
+    let expected = |image: RgbaImage| {
+        image.pixels.iter().map(|v| v[0]).collect::<Vec<_>>() == vec![0xFF, 0, 0, 0xFF, 0, 0]
+    };
+    assert!(expected(
+        read_netpbm(b"P1\n# comment\n3 2\n1 0 0\n100").unwrap()
+    ));
+    assert!(expected(read_netpbm(b"P4 3 2\n\x80\x80").unwrap()));
+    assert!(expected(
+        read_netpbm(b"P2\n3 2\n15\n15 0 0\n15 0 0\n").unwrap()
+    ));
+    assert!(expected(
+        read_netpbm(b"P5 3 2 255\n\xFF\x00\x00\xFF\x00\x00").unwrap()
+    ));
+    let image = read_netpbm(b"P5 2 1 65535\n\xFF\xFF\x7F\xFF").unwrap();
+    assert_eq!((image.pixels[0][0], image.pixels[1][0]), (0xFF, 0x7F));
+    assert!(read_netpbm(b"P5 3 2 255\n\xFF").is_err());
+    assert!(read_netpbm(b"P1 3").is_err());
+    assert!(read_netpbm(b"P4 4294967296 4294967297\n").is_err());
+    assert!(read_netpbm(b"P5 18446744073709551615 2 255\n").is_err());
+}
+
+/// Read X11 bitmap; the set bits (foreground) are read as white, i.e. the lit LEDs.
+fn read_xbm(data: &[u8]) -> Result<RgbaImage, BadgeImageReadError> {
+    let invalid =
+        |message: &str| BadgeImageReadError::InvalidImageData(format!("xbm: {}", message));
+    let text = std::str::from_utf8(data).map_err(|_| invalid("not a text file"))?;
+    let (declarations, bits) = text
+        .split_once('{')
+        .ok_or_else(|| invalid("bits not found"))?;
+    let bits = bits.split('}').next().unwrap_or_default();
+
+    let define = |suffix: &str| {
+        declarations
+            .lines()
+            .filter_map(|line| {
+                let mut tokens = line.split_whitespace();
+                (tokens.next() == Some("#define") && tokens.next()?.ends_with(suffix))
+                    .then(|| tokens.next()?.parse::<usize>().ok())?
+            })
+            .next()
+    };
+    let width = define("_width").ok_or_else(|| invalid("width not found"))?;
+    let height = define("_height").ok_or_else(|| invalid("height not found"))?;
+    // X10 bitmap is an array of 16-bit values
+    let word_bytes = if declarations.contains("short") { 2 } else { 1 };
+
+    let bytes = bits
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            let v = match v.strip_prefix("0x").or_else(|| v.strip_prefix("0X")) {
+                Some(hex) => u16::from_str_radix(hex, 16),
+                None => v.parse(),
+            };
+            v.map(|v| v.to_le_bytes())
+                .map_err(|_| invalid("wrong value"))
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flat_map(|v| v.into_iter().take(word_bytes))
+        .collect::<Vec<u8>>();
+
+    let stride = width.div_ceil(8 * word_bytes) * word_bytes;
+    if stride
+        .checked_mul(height)
+        .is_none_or(|size| bytes.len() < size)
+    {
+        return Err(invalid("truncated data"));
+    }
+    let mut image = RgbaImage::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let v = if bytes[y * stride + x / 8] & (1 << (x % 8)) != 0 {
+                0xFF
+            } else {
+                0x00
+            };
+            image.pixels[y * width + x] = [v, v, v, 0xFF];
+        }
+    }
+    Ok(image)
+}
+
+#[test]
+fn test_read_xbm() {
+    let xbm = b"#define test_width 10
+#define test_height 2
+static unsigned char test_bits[] = {
+   0x01, 0x02, 0x00, 0x03 };
+";
+    let image = read_xbm(xbm).unwrap();
+    assert_eq!((image.width, image.height), (10, 2));
+    let lit = image
+        .pixels
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| v[0] == 0xFF)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert_eq!(lit, vec![0, 9, 18, 19]);
+
+    let x10 = b"#define old_width 10\n#define old_height 1\nstatic short old_bits[] = { 0x0201 };";
+    let image = read_xbm(x10).unwrap();
+    assert_eq!(image.pixels[0][0], 0xFF);
+    assert_eq!(image.pixels[9][0], 0xFF);
+
+    assert!(read_xbm(b"#define a_width 8\nstatic char a_bits[] = { 0x00 };").is_err());
+    assert!(
+        read_xbm(
+            b"#define a_width 18446744073709551615\n#define a_height 2\nstatic char a_bits[] = { 0x00 };"
+        )
+        .is_err()
+    );
+}
+
+/// Read Windows bitmap of 1, 4, 8, 16, 24 or 32 bits per pixel without the run-length compression
+fn read_bmp(data: &[u8]) -> Result<RgbaImage, BadgeImageReadError> {
+    let invalid =
+        |message: &str| BadgeImageReadError::InvalidImageData(format!("bmp: {}", message));
+    let u16_at = |i: usize| {
+        data.get(i..i + 2)
+            .map(|v| u16::from_le_bytes([v[0], v[1]]) as u32)
+            .ok_or_else(|| invalid("truncated header"))
+    };
+    let u32_at = |i: usize| {
+        data.get(i..i + 4)
+            .map(|v| u32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+            .ok_or_else(|| invalid("truncated header"))
+    };
+
+    let pixel_offset = u32_at(10)? as usize;
+    let header_size = u32_at(14)? as usize;
+    if header_size < 40 {
+        return Err(invalid("OS/2 bitmap not supported"));
+    }
+    let width = u32_at(18)? as i32;
+    let height = u32_at(22)? as i32;
+    let bit_count = u16_at(28)?;
+    let compression = u32_at(30)?;
+    if width <= 0 || height == 0 {
+        return Err(invalid("wrong size"));
+    }
+    let (width, top_down) = (width as usize, height < 0);
+    let height = height.unsigned_abs() as usize;
+
+    // BI_RGB or BI_BITFIELDS
+    let masks = match (compression, bit_count) {
+        (0, 16) => [0x7C00, 0x03E0, 0x001F, 0],
+        (0, 32) => [0xFF0000, 0xFF00, 0xFF, 0],
+        (3, 16 | 32) => {
+            let base = if header_size >= 52 {
+                14 + 40
+            } else {
+                14 + header_size
+            };
+            let alpha = if header_size >= 56 {
+                u32_at(14 + 52)?
+            } else {
+                0
+            };
+            [u32_at(base)?, u32_at(base + 4)?, u32_at(base + 8)?, alpha]
+        }
+        (0, 1 | 4 | 8 | 24) => [0; 4],
+        _ => {
+            return Err(invalid(&format!(
+                "{} bits per pixel with compression {} not supported",
+                bit_count, compression
+            )));
+        }
+    };
+    let palette = if bit_count <= 8 {
+        let colors_used = u32_at(46)? as usize;
+        let count = if colors_used == 0 {
+            1 << bit_count
+        } else {
+            colors_used
+        };
+        (0..count)
+            .map(|i| {
+                let entry = 14 + header_size + 4 * i;
+                data.get(entry..entry + 3)
+                    .map(|v| [v[2], v[1], v[0], 0xFF])
+                    .ok_or_else(|| invalid("truncated palette"))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        Vec::new()
+    };
+
+    let channel = |v: u32, mask: u32| {
+        if mask == 0 {
+            return None;
+        }
+        let max = (mask >> mask.trailing_zeros()) as u64;
+        Some(((((v & mask) >> mask.trailing_zeros()) as u64) * 0xFF / max) as u8)
+    };
+    let stride = (bit_count as usize * width).div_ceil(32) * 4;
+    if stride
+        .checked_mul(height)
+        .and_then(|size| size.checked_add(pixel_offset))
+        .is_none_or(|end| data.len() < end)
+    {
+        return Err(invalid("truncated pixel data"));
+    }
+    let mut image = RgbaImage::new(width, height);
+    for y in 0..height {
+        let row_start = pixel_offset + stride * if top_down { y } else { height - 1 - y };
+        let row = data
+            .get(row_start..row_start + stride)
+            .ok_or_else(|| invalid("truncated pixel data"))?;
+        for x in 0..width {
+            image.pixels[y * width + x] = match bit_count {
+                1 | 4 | 8 => {
+                    let bits = bit_count as usize;
+                    let v = row[x * bits / 8] >> (8 - bits - x * bits % 8) & ((1 << bits) - 1);
+                    *palette
+                        .get(v as usize)
+                        .ok_or_else(|| invalid("wrong color index"))?
+                }
+                24 => [row[3 * x + 2], row[3 * x + 1], row[3 * x], 0xFF],
+                _ => {
+                    let v = if bit_count == 16 {
+                        u16::from_le_bytes([row[2 * x], row[2 * x + 1]]) as u32
+                    } else {
+                        u32::from_le_bytes([
+                            row[4 * x],
+                            row[4 * x + 1],
+                            row[4 * x + 2],
+                            row[4 * x + 3],
+                        ])
+                    };
+                    [
+                        channel(v, masks[0]).unwrap_or(0),
+                        channel(v, masks[1]).unwrap_or(0),
+                        channel(v, masks[2]).unwrap_or(0),
+                        channel(v, masks[3]).unwrap_or(0xFF),
+                    ]
+                }
+            };
+        }
+    }
+    Ok(image)
+}
+
+#[test]
+fn test_read_bmp() {
+    fn create_bmp_data(
+        width: i32,
+        height: i32,
+        bit_count: u16,
+        palette: &[u8],
+        pixels: &[u8],
+    ) -> Vec<u8> {
+        let pixel_offset = 14 + 40 + palette.len() as u32;
+        let mut data = Vec::new();
+        data.extend(b"BM");
+        data.extend((pixel_offset + pixels.len() as u32).to_le_bytes());
+        data.extend([0; 4]);
+        data.extend(pixel_offset.to_le_bytes());
+        data.extend(40u32.to_le_bytes());
+        data.extend(width.to_le_bytes());
+        data.extend(height.to_le_bytes());
+        data.extend(1u16.to_le_bytes());
+        data.extend(bit_count.to_le_bytes());
+        data.extend([0; 24]);
+        data.extend(palette);
+        data.extend(pixels);
+        data
+    }
+    let lit = |image: RgbaImage| {
+        image
+            .pixels
+            .iter()
+            .map(|v| (v[0] as u32 + v[1] as u32 + v[2] as u32 > 0x180) as u8)
+            .collect::<Vec<_>>()
+    };
+
+    // bottom-up 1-bit bitmap with black and white palette
+    let palette = [0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0];
+    let data = create_bmp_data(3, 2, 1, &palette, &[0x20, 0, 0, 0, 0x80, 0, 0, 0]);
+    assert_eq!(lit(read_bmp(&data).unwrap()), vec![1, 0, 0, 0, 0, 1]);
+
+    // top-down 24-bit bitmap
+    #[rustfmt::skip]
+    let pixels = [
+        0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0x00,
+    ];
+    let data = create_bmp_data(2, -2, 24, &[], &pixels);
+    assert_eq!(lit(read_bmp(&data).unwrap()), vec![1, 0, 0, 1]);
+
+    // 32-bit bitmap
+    let data = create_bmp_data(2, 1, 32, &[], &[0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0]);
+    assert_eq!(lit(read_bmp(&data).unwrap()), vec![1, 0]);
+
+    assert!(read_bmp(&data[0..60]).is_err());
+    let data = create_bmp_data(i32::MAX, i32::MIN + 1, 32, &[], &[0; 8]);
+    assert!(read_bmp(&data).is_err());
+}
+
+/// Kinds of the image files detected by the magic bytes
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum ImageKind {
+    Png,
+    Gif,
+    Bmp,
+    Netpbm,
+    Xbm,
+    Svg,
+}
+
+fn detect_image_kind(signature: &[u8]) -> Option<ImageKind> {
+    if signature.starts_with(PNG_SIGNATURE) {
+        Some(ImageKind::Png)
+    } else if signature.starts_with(GIF_SIGNATURE) {
+        Some(ImageKind::Gif)
+    } else if signature.starts_with(b"BM") {
+        Some(ImageKind::Bmp)
+    } else if [b"P1", b"P2", b"P4", b"P5"]
+        .iter()
+        .any(|magic| signature.starts_with(*magic))
+    {
+        Some(ImageKind::Netpbm)
+    } else if signature.trim_ascii_start().starts_with(b"#define") {
+        Some(ImageKind::Xbm)
+    } else if svg::is_svg(signature) {
+        Some(ImageKind::Svg)
+    } else {
+        None
+    }
+}
+
+/// Returns true if the data starts with the png signature
+pub fn is_png(signature: &[u8]) -> bool {
+    detect_image_kind(signature) == Some(ImageKind::Png)
+}
+
+/// Decode the image file of png, gif, bmp, pbm/pgm, xbm or svg cropped to the region
+fn read_image<R: Read + BufRead + Seek>(
+    mut reader: R,
+    options: &ImageImportOptions,
+) -> Result<RgbaImage, BadgeImageReadError> {
+    let kind = detect_image_kind(reader.fill_buf()?);
+    let image = match kind {
+        Some(ImageKind::Png) => read_png_image(reader)?.0,
+        Some(ImageKind::Gif) => read_gif_image(reader)?,
+        Some(kind) => {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            match kind {
+                ImageKind::Bmp => read_bmp(&data)?,
+                ImageKind::Netpbm => read_netpbm(&data)?,
+                ImageKind::Svg => svg::render_svg(&data, options)?,
+                _ => read_xbm(&data)?,
+            }
+        }
+        None => return Err(BadgeImageReadError::UnknownImageFormat),
+    };
+    crop_to_region(image, options)
+}
+
+/// Read the image file of png, gif, bmp, pbm/pgm, xbm or svg
+pub fn read_image_to_canvas<R: Read + BufRead + Seek>(
+    reader: R,
+    options: &ImageImportOptions,
+) -> Result<Canvas, BadgeImageReadError> {
+    Ok(image_to_canvas(&read_image(reader, options)?, options))
+}
+
+/// Read the sprite sheet and slice it into the cells of the grid in row-major order
+///
+/// The columns and rows left over by the cells of the equal size are ignored.
+pub fn read_image_grid_to_canvases<R: Read + BufRead + Seek>(
+    reader: R,
+    grid: ImageGrid,
+    options: &ImageImportOptions,
+) -> Result<Vec<Canvas>, BadgeImageReadError> {
+    let image = read_image(reader, options)?;
+    let cell_width = image.width / grid.columns;
+    let cell_height = image.height / grid.rows;
+    if cell_width == 0 || cell_height == 0 {
+        return Err(BadgeImageReadError::InvalidImageData(format!(
+            "{}x{} image is too small for the {}x{} grid",
+            image.width, image.height, grid.columns, grid.rows
+        )));
+    }
+    let cells = (0..grid.rows).flat_map(|row| (0..grid.columns).map(move |column| (column, row)));
+    Ok(cells
+        .map(|(column, row)| {
+            let cell = image.crop(
+                column * cell_width,
+                row * cell_height,
+                cell_width,
+                cell_height,
+            );
+            image_to_canvas(&cell, options)
+        })
+        .collect())
+}
+
+#[test]
+fn test_read_image_region_and_grid() {
+    // 2x1 cells of 3x11 pixels; the left cell has the lit column at x=0 and the right at x=5
+    let mut pbm = b"P1 6 11\n".to_vec();
+    pbm.extend(b"100001\n".repeat(BADGE_MSG_FONT_HEIGHT));
+
+    let options = ImageImportOptions {
+        region: Some(ImageRegion {
+            x: 3,
+            y: 0,
+            width: 3,
+            height: BADGE_MSG_FONT_HEIGHT,
+        }),
+        ..Default::default()
+    };
+    let canvas = read_image_to_canvas(Cursor::new(&pbm), &options).unwrap();
+    assert_eq!(canvas.width, 3);
+    assert_eq!(canvas.content_columns(), Some(2..3));
+
+    let options = ImageImportOptions {
+        region: Some(ImageRegion {
+            x: 4,
+            y: 0,
+            width: 3,
+            height: BADGE_MSG_FONT_HEIGHT,
+        }),
+        ..Default::default()
+    };
+    assert!(matches!(
+        read_image_to_canvas(Cursor::new(&pbm), &options),
+        Err(BadgeImageReadError::InvalidImageData(_))
+    ));
+    let options = ImageImportOptions {
+        region: Some(ImageRegion {
+            x: usize::MAX,
+            y: 0,
+            width: 2,
+            height: BADGE_MSG_FONT_HEIGHT,
+        }),
+        ..Default::default()
+    };
+    assert!(matches!(
+        read_image_to_canvas(Cursor::new(&pbm), &options),
+        Err(BadgeImageReadError::InvalidImageData(_))
+    ));
+
+    let grid = ImageGrid {
+        columns: 2,
+        rows: 1,
+    };
+    let options = ImageImportOptions::default();
+    let cells = read_image_grid_to_canvases(Cursor::new(&pbm), grid, &options).unwrap();
+    assert_eq!(cells.len(), 2);
+    assert_eq!(cells[0].content_columns(), Some(0..1));
+    assert_eq!(cells[1].content_columns(), Some(2..3));
+
+    let grid = ImageGrid {
+        columns: 7,
+        rows: 1,
+    };
+    assert!(read_image_grid_to_canvases(Cursor::new(&pbm), grid, &options).is_err());
+}
+
+#[test]
+fn test_read_image_to_canvas() {
+    let options = ImageImportOptions::default();
+    let read = |data: &[u8]| {
+        read_image_to_canvas(Cursor::new(data), &options).map(|canvas| canvas2vec(&canvas))
+    };
+
+    let mut pbm = b"P1 8 11\n".to_vec();
+    pbm.extend(b"10000001\n".repeat(BADGE_MSG_FONT_HEIGHT));
+    assert_eq!(
+        read(&pbm).unwrap(),
+        vec![0b1000_0001; BADGE_MSG_FONT_HEIGHT]
+    );
+
+    let mut png_data = Vec::new();
+    write_badge_message_to_png(
+        &[0xF0; BADGE_MSG_FONT_HEIGHT],
+        &[("key", "value".to_string())],
+        &mut png_data,
+    )
+    .unwrap();
+    assert!(is_png(&png_data));
+    assert_eq!(read(&png_data).unwrap(), vec![0xF0; BADGE_MSG_FONT_HEIGHT]);
+
+    let mut gif_data = Vec::new();
+    {
+        let mut encoder =
+            gif::Encoder::new(&mut gif_data, 1, 1, &[0, 0, 0, 0xFF, 0xFF, 0xFF]).unwrap();
+        encoder
+            .write_frame(&gif::Frame {
+                width: 1,
+                height: 1,
+                buffer: std::borrow::Cow::Borrowed(&[1]),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+    // 1x1 image is scaled up to the message height
+    assert_eq!(
+        read(&gif_data).unwrap(),
+        [[0xFF; BADGE_MSG_FONT_HEIGHT], [0xE0; BADGE_MSG_FONT_HEIGHT]].concat()
+    );
+
+    assert!(matches!(
+        read(b"unknown"),
+        Err(BadgeImageReadError::UnknownImageFormat)
+    ));
+}
+
+/// Identifier of the message data in the exported source code and xbm
+const EXPORT_NAME: &str = "badge_message";
+
+/// Write the message data as binary PBM (P4); the lit pixels are the set bits.
+pub fn write_badge_message_to_pbm<W: Write>(
+    message_data: &[u8],
+    mut writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let canvas = vec2canvas(message_data, BADGE_MSG_FONT_HEIGHT);
+    write!(writer, "P4\n{} {}\n", canvas.width, canvas.height)?;
+    for y in 0..canvas.height {
+        // message data is always a multiple of 8 columns wide
+        let row = (0..canvas.width / 8)
+            .map(|i| message_data[i * BADGE_MSG_FONT_HEIGHT + y])
+            .collect::<Vec<_>>();
+        writer.write_all(&row)?;
+    }
+    Ok(())
+}
+
+/// Write the message data as X11 bitmap; the lit pixels are the set bits.
+pub fn write_badge_message_to_xbm<W: Write>(
+    message_data: &[u8],
+    mut writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let canvas = vec2canvas(message_data, BADGE_MSG_FONT_HEIGHT);
+    let bytes = (0..canvas.height)
+        .flat_map(|y| {
+            (0..canvas.width / 8).map(move |i| {
+                // xbm has the leftmost pixel in the least significant bit
+                message_data[i * BADGE_MSG_FONT_HEIGHT + y].reverse_bits()
+            })
+        })
+        .map(|v| format!("0x{:02x}", v))
+        .collect::<Vec<_>>();
+
+    writeln!(writer, "#define {}_width {}", EXPORT_NAME, canvas.width)?;
+    writeln!(writer, "#define {}_height {}", EXPORT_NAME, canvas.height)?;
+    writeln!(writer, "static unsigned char {}_bits[] = {{", EXPORT_NAME)?;
+    for (i, line) in bytes.chunks(12).enumerate() {
+        let separator = if (i + 1) * 12 < bytes.len() { "," } else { "" };
+        writeln!(writer, "   {}{}", line.join(", "), separator)?;
+    }
+    writeln!(writer, "}};")?;
+    Ok(())
+}
+
+/// Hex bytes of the message data, one line per 8 columns as the badge receives them
+fn wire_layout_lines(message_data: &[u8]) -> Vec<String> {
+    message_data
+        .chunks(BADGE_MSG_FONT_HEIGHT)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .map(|v| format!("0x{:02X},", v))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+/// Write the message data as C header of a byte array in the wire layout
+pub fn write_badge_message_to_c<W: Write>(
+    message_data: &[u8],
+    mut writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let width = 8 * message_data.len() / BADGE_MSG_FONT_HEIGHT;
+    let guard = EXPORT_NAME.to_uppercase();
+    writeln!(writer, "#ifndef {}_H", guard)?;
+    writeln!(writer, "#define {}_H", guard)?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "/* {}x{} pixels; {} bytes (rows) per 8 columns, MSB is the leftmost */",
+        width, BADGE_MSG_FONT_HEIGHT, BADGE_MSG_FONT_HEIGHT
+    )?;
+    writeln!(writer, "#define {}_WIDTH {}", guard, width)?;
+    writeln!(writer, "#define {}_HEIGHT {}", guard, BADGE_MSG_FONT_HEIGHT)?;
+    writeln!(
+        writer,
+        "static const unsigned char {}[{}] = {{",
+        EXPORT_NAME,
+        message_data.len()
+    )?;
+    for line in wire_layout_lines(message_data) {
+        writeln!(writer, "    {}", line)?;
+    }
+    writeln!(writer, "}};")?;
+    writeln!(writer)?;
+    writeln!(writer, "#endif /* {}_H */", guard)?;
+    Ok(())
+}
+
+/// Write the message data as Rust `const` byte array in the wire layout
+pub fn write_badge_message_to_rust<W: Write>(
+    message_data: &[u8],
+    mut writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let width = 8 * message_data.len() / BADGE_MSG_FONT_HEIGHT;
+    writeln!(
+        writer,
+        "/// {}x{} pixels; {} bytes (rows) per 8 columns, MSB is the leftmost",
+        width, BADGE_MSG_FONT_HEIGHT, BADGE_MSG_FONT_HEIGHT
+    )?;
+    writeln!(
+        writer,
+        "pub const {}: [u8; {}] = [",
+        EXPORT_NAME.to_uppercase(),
+        message_data.len()
+    )?;
+    for line in wire_layout_lines(message_data) {
+        writeln!(writer, "    {}", line)?;
+    }
+    writeln!(writer, "];")?;
+    Ok(())
+}
+
+/// Write the message data as JSON of the size and the matrix of rows; 1 is the lit pixel.
+pub fn write_badge_message_to_json<W: Write>(
+    message_data: &[u8],
+    mut writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let canvas = vec2canvas(message_data, BADGE_MSG_FONT_HEIGHT);
+    let rows = (0..canvas.height)
+        .map(|y| {
+            let row = (0..canvas.width)
+                .map(|x| (canvas.get(x, y) as u8).to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("    [{}]", row)
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+    writeln!(
+        writer,
+        "{{\n  \"width\": {},\n  \"height\": {},\n  \"rows\": [\n{}\n  ]\n}}",
+        canvas.width, canvas.height, rows
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_write_badge_message_exporters() {
+    #[rustfmt::skip]
+    let message_data = [
+        0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+        0xF0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let export = |write: fn(&[u8], &mut Vec<u8>) -> Result<(), BadgeImageWriteError>| {
+        let mut output = Vec::new();
+        write(&message_data, &mut output).unwrap();
+        output
+    };
+
+    let pbm = export(|d, w| write_badge_message_to_pbm(d, w));
+    assert!(pbm.starts_with(b"P4\n16 11\n\x80\xF0\x00\x00"));
+    assert_eq!(pbm.len(), 9 + 2 * BADGE_MSG_FONT_HEIGHT);
+    assert_eq!(
+        canvas2vec(&image_to_canvas(
+            &read_netpbm(&pbm).unwrap(),
+            &ImageImportOptions::default()
+        )),
+        message_data
+    );
+
+    let xbm = export(|d, w| write_badge_message_to_xbm(d, w));
+    assert!(String::from_utf8(xbm.clone()).unwrap().starts_with(
+        "#define badge_message_width 16\n#define badge_message_height 11\n\
+         static unsigned char badge_message_bits[] = {\n   0x01, 0x0f, 0x00,"
+    ));
+    assert_eq!(
+        canvas2vec(&image_to_canvas(
+            &read_xbm(&xbm).unwrap(),
+            &ImageImportOptions::default()
+        )),
+        message_data
+    );
+
+    let c = String::from_utf8(export(|d, w| write_badge_message_to_c(d, w))).unwrap();
+    assert!(c.contains("#define BADGE_MESSAGE_WIDTH 16\n"));
+    assert!(c.contains(
+        "static const unsigned char badge_message[22] = {\n    \
+         0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,\n    \
+         0xF0, 0x00,"
+    ));
+
+    let rust = String::from_utf8(export(|d, w| write_badge_message_to_rust(d, w))).unwrap();
+    assert!(rust.contains("pub const BADGE_MESSAGE: [u8; 22] = [\n    0x80, 0x00,"));
+    assert!(rust.ends_with("0x00,\n];\n"));
+
+    let json = String::from_utf8(export(|d, w| write_badge_message_to_json(d, w))).unwrap();
+    assert!(json.contains("\"width\": 16,"));
+    assert!(json.contains("    [1,0,0,0,0,0,0,0,1,1,1,1,0,0,0,0],\n"));
+    assert!(json.contains("    [0,0,0,0,0,0,0,1,0,0,0,0,0,0,0,0]\n  ]"));
+}
+
+/// Write the message data as is, i.e. 11 bytes (rows) per 8 columns
+pub fn write_badge_message_to_raw<W: Write>(
+    message_data: &[u8],
+    mut writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    writer.write_all(message_data)?;
+    Ok(())
+}
+
+/// Write the message data as hex text, one line of 11 bytes per 8 columns
+pub fn write_badge_message_to_hex<W: Write>(
+    message_data: &[u8],
+    mut writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    for chunk in message_data.chunks(BADGE_MSG_FONT_HEIGHT) {
+        let line = chunk
+            .iter()
+            .map(|v| format!("{:02x}", v))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Read the message data in the wire layout, i.e. 11 bytes (rows) per 8 columns
+///
+/// If `width` is given, the data must be exactly as long as the columns need
+/// and the padding columns of the last byte are cleared.
+pub fn read_raw_badge_message(
+    data: &[u8],
+    width: Option<usize>,
+) -> Result<Vec<u8>, BadgeImageReadError> {
+    let expected_len = match width {
+        Some(width) => width
+            .div_ceil(8)
+            .checked_mul(BADGE_MSG_FONT_HEIGHT)
+            .ok_or_else(|| {
+                BadgeImageReadError::InvalidImageData(format!("width {} is too large", width))
+            })?,
+        None => data.len().next_multiple_of(BADGE_MSG_FONT_HEIGHT),
+    };
+    if data.len() != expected_len {
+        return Err(BadgeImageReadError::InvalidImageData(format!(
+            "{} bytes of raw data; expected {} bytes",
+            data.len(),
+            expected_len
+        )));
+    }
+
+    let mut message_data = data.to_vec();
+    if let Some(width) = width.filter(|width| width % 8 != 0) {
+        let mask = !(0xFFu8 >> (width % 8));
+        let last_chunk = message_data.len() - BADGE_MSG_FONT_HEIGHT;
+        for v in &mut message_data[last_chunk..] {
+            *v &= mask;
+        }
+    }
+    Ok(message_data)
+}
+
+/// Parse the hex text of the message data in the wire layout
+///
+/// The bytes are separated by the whitespaces or the commas and may have the `0x` prefix.
+/// Runs of hex digits such as `80ff` are read as consecutive bytes,
+/// and the lines starting with `#` are comments.
+pub fn read_hex_badge_message(
+    text: &str,
+    width: Option<usize>,
+) -> Result<Vec<u8>, BadgeImageReadError> {
+    let invalid =
+        |token: &str| BadgeImageReadError::InvalidImageData(format!("hex: wrong byte '{}'", token));
+    let mut data = Vec::new();
+    for token in text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+        .filter(|token| !token.is_empty())
+    {
+        let digits = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        if digits.is_empty() || digits.len() % 2 != 0 || !digits.is_ascii() {
+            return Err(invalid(token));
+        }
+        for i in (0..digits.len()).step_by(2) {
+            data.push(u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| invalid(token))?);
+        }
+    }
+    read_raw_badge_message(&data, width)
+}
+
+#[test]
+fn test_raw_badge_message() {
+    let message_data = (0..2 * BADGE_MSG_FONT_HEIGHT as u8).collect::<Vec<_>>();
+
+    let mut raw = Vec::new();
+    write_badge_message_to_raw(&message_data, &mut raw).unwrap();
+    assert_eq!(raw, message_data);
+    assert_eq!(read_raw_badge_message(&raw, None).unwrap(), message_data);
+    assert!(read_raw_badge_message(&raw[1..], None).is_err());
+    assert!(read_raw_badge_message(&raw, Some(17)).is_err());
+    assert!(read_raw_badge_message(&raw, Some(usize::MAX)).is_err());
+    let masked = read_raw_badge_message(&[0xFF; BADGE_MSG_FONT_HEIGHT], Some(3)).unwrap();
+    assert_eq!(masked, vec![0xE0; BADGE_MSG_FONT_HEIGHT]);
+
+    let mut hex = Vec::new();
+    write_badge_message_to_hex(&message_data, &mut hex).unwrap();
+    let hex = String::from_utf8(hex).unwrap();
+    assert!(hex.starts_with("00 01 02 03 04 05 06 07 08 09 0a\n0b 0c"));
+    assert_eq!(
+        read_hex_badge_message(&hex, Some(16)).unwrap(),
+        message_data
+    );
+
+    let text = "# comment\n0x80, 0x00,\n00000000 00000000 00\n";
+    assert_eq!(
+        read_hex_badge_message(text, None).unwrap(),
+        [vec![0x80], vec![0; BADGE_MSG_FONT_HEIGHT - 1]].concat()
+    );
+    assert!(read_hex_badge_message("0x8", None).is_err());
+    assert!(read_hex_badge_message("zz", None).is_err());
+}
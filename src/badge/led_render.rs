@@ -0,0 +1,303 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::badge::canvas::Canvas;
+use crate::badge::raster::RgbaImage;
+
+/// RGB color
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    /// Mix with `other` color; `ratio` is the weight of `other` in [0.0..1.0].
+    pub fn mix(&self, other: Color, ratio: f32) -> Color {
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * ratio).round() as u8;
+        Color::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+        )
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl FromStr for Color {
+    type Err = ();
+
+    /// Parse `#rrggbb` or `rrggbb`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let hex = value.strip_prefix('#').unwrap_or(value);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(());
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| ());
+        Ok(Color::new(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+#[test]
+fn test_color_from_str() {
+    assert_eq!(
+        Color::from_str("#FF8000").unwrap(),
+        Color::new(0xFF, 0x80, 0x00)
+    );
+    assert_eq!(
+        Color::from_str("0a0b0c").unwrap(),
+        Color::new(0x0A, 0x0B, 0x0C)
+    );
+    assert_eq!(Color::from_str("#FF80"), Err(()));
+    assert_eq!(Color::from_str("#GG8000"), Err(()));
+    assert_eq!(Color::new(0xFF, 0x80, 0x00).to_string(), "#ff8000");
+}
+
+/// Shape of a LED dot
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum DotShape {
+    Square,
+    Round,
+}
+
+impl DotShape {
+    pub fn values() -> impl Iterator<Item = DotShape> {
+        [DotShape::Square, DotShape::Round].into_iter()
+    }
+}
+
+impl fmt::Display for DotShape {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(format!("{:?}", self).to_lowercase().as_str())
+    }
+}
+
+impl FromStr for DotShape {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        DotShape::values()
+            .find(|&v| v.to_string().as_str() == value)
+            .ok_or(())
+    }
+}
+
+/// Options of the rendering which looks like the LED matrix of the badge
+#[derive(Debug, Clone)]
+pub struct LedRenderOptions {
+    /// Size of a LED cell in pixels
+    pub scale: usize,
+    /// Shape of the LED dots
+    pub shape: DotShape,
+    /// Gap between the LED dots in pixels
+    pub gap: usize,
+    /// Color of the lit LED
+    pub lit_color: Color,
+    /// Color of the unlit LED
+    pub unlit_color: Color,
+    /// Color between the LEDs
+    pub background_color: Color,
+    /// Width of the bezel around the LED matrix in pixels; 0 for no bezel
+    pub bezel: usize,
+    /// Color of the bezel
+    pub bezel_color: Color,
+}
+
+impl Default for LedRenderOptions {
+    fn default() -> Self {
+        LedRenderOptions {
+            scale: 8,
+            shape: DotShape::Round,
+            gap: 1,
+            lit_color: Color::new(0xFF, 0x30, 0x10),
+            unlit_color: Color::new(0x30, 0x10, 0x10),
+            background_color: Color::new(0x00, 0x00, 0x00),
+            bezel: 0,
+            bezel_color: Color::new(0x40, 0x40, 0x40),
+        }
+    }
+}
+
+impl LedRenderOptions {
+    /// Size of a LED dot in a cell
+    fn dot_size(&self) -> f32 {
+        self.scale.saturating_sub(self.gap).max(1) as f32
+    }
+
+    /// Returns true if the point (x, y) relative to the cell origin is on the dot
+    fn is_on_dot(&self, x: f32, y: f32) -> bool {
+        let center = self.scale as f32 / 2.0;
+        let half = self.dot_size() / 2.0;
+        match self.shape {
+            DotShape::Square => (x - center).abs() <= half && (y - center).abs() <= half,
+            DotShape::Round => (x - center).powi(2) + (y - center).powi(2) <= half * half,
+        }
+    }
+}
+
+/// Number of sub-samples per pixel along an axis to anti-alias the edges of the dots
+const SUPER_SAMPLING: usize = 4;
+
+/// Render the canvas as the round or square LED dots given by `options.shape`
+pub(crate) fn render_leds(canvas: &Canvas, options: &LedRenderOptions) -> RgbaImage {
+    let options = &LedRenderOptions {
+        scale: options.scale.max(1),
+        ..options.clone()
+    };
+    let (scale, bezel) = (options.scale, options.bezel);
+    let mut image = RgbaImage::new(
+        canvas.width * scale + 2 * bezel,
+        canvas.height * scale + 2 * bezel,
+    );
+    let Color { r, g, b } = options.bezel_color;
+    image.pixels.fill([r, g, b, 0xFF]);
+
+    // coverage ratio of the dot of each pixel in a LED cell
+    let coverage = (0..scale * scale)
+        .map(|i| {
+            let (p, q) = (i % scale, i / scale);
+            let covered = (0..SUPER_SAMPLING * SUPER_SAMPLING)
+                .filter(|j| {
+                    let sx = p as f32 + ((j % SUPER_SAMPLING) as f32 + 0.5) / SUPER_SAMPLING as f32;
+                    let sy = q as f32 + ((j / SUPER_SAMPLING) as f32 + 0.5) / SUPER_SAMPLING as f32;
+                    options.is_on_dot(sx, sy)
+                })
+                .count();
+            covered as f32 / (SUPER_SAMPLING * SUPER_SAMPLING) as f32
+        })
+        .collect::<Vec<_>>();
+
+    for y in 0..canvas.height {
+        for x in 0..canvas.width {
+            let color = if canvas.get(x, y) {
+                options.lit_color
+            } else {
+                options.unlit_color
+            };
+            for (i, &ratio) in coverage.iter().enumerate() {
+                let p = bezel + x * scale + i % scale;
+                let q = bezel + y * scale + i / scale;
+                let Color { r, g, b } = options.background_color.mix(color, ratio);
+                image.pixels[q * image.width + p] = [r, g, b, 0xFF];
+            }
+        }
+    }
+    image
+}
+
+#[test]
+fn test_render_leds() {
+    let mut canvas = Canvas::new(2, 1);
+    canvas.set(1, 0, true);
+    let options = LedRenderOptions {
+        scale: 8,
+        lit_color: Color::new(0xFF, 0x00, 0x00),
+        unlit_color: Color::new(0x00, 0x00, 0xFF),
+        background_color: Color::new(0x00, 0x00, 0x00),
+        ..Default::default()
+    };
+    let image = render_leds(&canvas, &options);
+    assert_eq!((image.width, image.height), (16, 8));
+    // corners are the background, centers are the LED colors
+    assert_eq!(image.pixels[0], [0x00, 0x00, 0x00, 0xFF]);
+    assert_eq!(image.pixels[4 * 16 + 4], [0x00, 0x00, 0xFF, 0xFF]);
+    assert_eq!(image.pixels[4 * 16 + 12], [0xFF, 0x00, 0x00, 0xFF]);
+    assert_eq!(image.pixels[8 + 7], [0x00, 0x00, 0x00, 0xFF]);
+
+    let options = LedRenderOptions {
+        shape: DotShape::Square,
+        gap: 0,
+        bezel: 2,
+        ..options
+    };
+    let image = render_leds(&canvas, &options);
+    assert_eq!((image.width, image.height), (20, 12));
+    assert_eq!(image.pixels[0], [0x40, 0x40, 0x40, 0xFF]);
+    assert_eq!(image.pixels[2 * 20 + 2], [0x00, 0x00, 0xFF, 0xFF]);
+    assert_eq!(image.pixels[2 * 20 + 10], [0xFF, 0x00, 0x00, 0xFF]);
+}
+
+/// Write the canvas as the LED-look SVG image
+pub(crate) fn write_leds_to_svg<W: Write>(
+    canvas: &Canvas,
+    options: &LedRenderOptions,
+    mut writer: W,
+) -> std::io::Result<()> {
+    let (scale, bezel) = (options.scale.max(1), options.bezel);
+    let width = canvas.width * scale + 2 * bezel;
+    let height = canvas.height * scale + 2 * bezel;
+    let half = options.dot_size() / 2.0;
+
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        width, height
+    )?;
+    if bezel > 0 {
+        writeln!(
+            writer,
+            r#"<rect width="{}" height="{}" fill="{}"/>"#,
+            width, height, options.bezel_color
+        )?;
+    }
+    writeln!(
+        writer,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        bezel,
+        bezel,
+        canvas.width * scale,
+        canvas.height * scale,
+        options.background_color
+    )?;
+
+    for (lit, color) in [(false, options.unlit_color), (true, options.lit_color)] {
+        writeln!(writer, r#"<g fill="{}">"#, color)?;
+        for y in 0..canvas.height {
+            for x in (0..canvas.width).filter(|&x| canvas.get(x, y) == lit) {
+                let cx = (bezel + x * scale) as f32 + scale as f32 / 2.0;
+                let cy = (bezel + y * scale) as f32 + scale as f32 / 2.0;
+                match options.shape {
+                    DotShape::Square => writeln!(
+                        writer,
+                        r#"<rect x="{}" y="{}" width="{2}" height="{2}"/>"#,
+                        cx - half,
+                        cy - half,
+                        2.0 * half
+                    )?,
+                    DotShape::Round => {
+                        writeln!(writer, r#"<circle cx="{}" cy="{}" r="{}"/>"#, cx, cy, half)?
+                    }
+                }
+            }
+        }
+        writeln!(writer, "</g>")?;
+    }
+    writeln!(writer, "</svg>")
+}
+
+#[test]
+fn test_write_leds_to_svg() {
+    let mut canvas = Canvas::new(2, 1);
+    canvas.set(1, 0, true);
+    let mut output = Vec::new();
+    write_leds_to_svg(&canvas, &LedRenderOptions::default(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="8""#));
+    assert!(output.contains(
+        "<g fill=\"#301010\">\n<circle cx=\"4\" cy=\"4\" r=\"3.5\"/>\n</g>\n\
+         <g fill=\"#ff3010\">\n<circle cx=\"12\" cy=\"4\" r=\"3.5\"/>\n</g>"
+    ));
+    assert!(output.ends_with("</svg>\n"));
+}
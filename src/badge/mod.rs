@@ -8,17 +8,36 @@ use std::mem;
 use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
+pub use crate::badge::bitmap_font::BUILTIN_FONT_NAME;
+pub use crate::badge::canvas::Canvas;
+use crate::badge::canvas::{canvas2vec, vec2canvas};
 use crate::badge::device::BadgeType;
 pub use crate::badge::error::BadgeError;
 use crate::badge::font_selector::select_font;
+pub use crate::badge::image_io::{ImageGrid, ImageImportOptions, ImageRegion};
+pub use crate::badge::led_render::{Color, DotShape, LedRenderOptions};
+pub use crate::badge::raster::{DitherMethod, GrayChannel};
+pub use crate::badge::terminal::{GraphicsProtocol, PreviewStyle};
 use crate::badge::text::render_text;
+pub use crate::badge::text::{TextRenderOptions, is_right_to_left};
 
+mod bitmap_font;
+mod canvas;
 pub mod device;
 mod error;
 mod font_selector;
+mod html;
 mod image_io;
+mod led_render;
+mod raster;
+mod simulator;
+mod sprite_sheet;
+mod svg;
+mod terminal;
 mod text;
+mod text_bitmap;
 
 /// Number of messages stored in the LED Badge
 pub const N_MESSAGES: usize = 8;
@@ -32,6 +51,9 @@ const DISP_SIZE: usize = 32767;
 /// Height of the message
 const BADGE_MSG_FONT_HEIGHT: usize = 11;
 
+/// Maximum width of a message in columns, which fills the display memory
+pub const MAX_MESSAGE_WIDTH: usize = DISP_SIZE / BADGE_MSG_FONT_HEIGHT * 8;
+
 /// Message effect type
 #[derive(Debug, PartialEq, Copy, Clone)]
 #[allow(dead_code)]
@@ -79,7 +101,7 @@ impl TryFrom<u8> for BadgeEffect {
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         if value <= (BadgeEffect::Laser as u8) {
-            Ok(unsafe { mem::transmute(value) })
+            Ok(unsafe { mem::transmute::<u8, BadgeEffect>(value) })
         } else {
             Err(())
         }
@@ -119,6 +141,151 @@ fn badge_effect_from_str() {
     assert_eq!(BadgeEffect::from_str("left2"), Err(()));
 }
 
+/// Horizontal alignment of the message
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum BadgeAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+impl BadgeAlignment {
+    pub fn values() -> impl Iterator<Item = BadgeAlignment> {
+        [
+            BadgeAlignment::Left,
+            BadgeAlignment::Center,
+            BadgeAlignment::Right,
+        ]
+        .into_iter()
+    }
+}
+
+impl fmt::Display for BadgeAlignment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(format!("{:?}", self).to_lowercase().as_str())
+    }
+}
+
+impl FromStr for BadgeAlignment {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        BadgeAlignment::values()
+            .find(|&v| v.to_string().as_str() == value)
+            .ok_or(())
+    }
+}
+
+#[test]
+fn test_badge_alignment_from_str() {
+    assert_eq!(
+        BadgeAlignment::from_str("center").unwrap(),
+        BadgeAlignment::Center
+    );
+    assert_eq!(BadgeAlignment::Right.to_string(), String::from("right"));
+    assert_eq!(BadgeAlignment::from_str("middle"), Err(()));
+}
+
+/// Animated image format
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum AnimationFormat {
+    Gif,
+    Png,
+}
+
+/// Image file format of the LED-look rendering
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum LedImageFormat {
+    Png,
+    Svg,
+}
+
+/// File format of the message bitmap
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum MessageFormat {
+    /// Grayscale png with the message settings in the text chunks
+    Png,
+    /// Binary portable bitmap
+    Pbm,
+    /// X11 bitmap
+    Xbm,
+    /// C header of the byte array in the wire layout
+    C,
+    /// Rust `const` byte array in the wire layout
+    Rust,
+    /// JSON matrix of the rows
+    Json,
+    /// Raw bytes in the wire layout
+    Raw,
+    /// Hex text of the bytes in the wire layout
+    Hex,
+}
+
+impl MessageFormat {
+    pub fn values() -> impl Iterator<Item = MessageFormat> {
+        [
+            MessageFormat::Png,
+            MessageFormat::Pbm,
+            MessageFormat::Xbm,
+            MessageFormat::C,
+            MessageFormat::Rust,
+            MessageFormat::Json,
+            MessageFormat::Raw,
+            MessageFormat::Hex,
+        ]
+        .into_iter()
+    }
+
+    /// Guess the format from the file extension
+    pub fn from_extension(extension: &str) -> Option<MessageFormat> {
+        match extension.to_lowercase().as_str() {
+            "png" => Some(MessageFormat::Png),
+            "pbm" => Some(MessageFormat::Pbm),
+            "xbm" => Some(MessageFormat::Xbm),
+            "c" | "h" => Some(MessageFormat::C),
+            "rs" => Some(MessageFormat::Rust),
+            "json" => Some(MessageFormat::Json),
+            "bin" | "raw" => Some(MessageFormat::Raw),
+            "hex" => Some(MessageFormat::Hex),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MessageFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(format!("{:?}", self).to_lowercase().as_str())
+    }
+}
+
+impl FromStr for MessageFormat {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        MessageFormat::values()
+            .find(|&v| v.to_string().as_str() == value)
+            .ok_or(())
+    }
+}
+
+#[test]
+fn test_message_format() {
+    assert_eq!(MessageFormat::from_extension("H"), Some(MessageFormat::C));
+    assert_eq!(
+        MessageFormat::from_extension("rs"),
+        Some(MessageFormat::Rust)
+    );
+    assert_eq!(
+        MessageFormat::from_extension("bin"),
+        Some(MessageFormat::Raw)
+    );
+    assert_eq!(MessageFormat::from_extension("txt"), None);
+    assert_eq!(
+        MessageFormat::from_str("json").unwrap(),
+        MessageFormat::Json
+    );
+}
+
 /// Value range of text animation speed
 pub const BADGE_SPEED_RANGE: RangeInclusive<u8> = 1..=8;
 
@@ -138,6 +305,10 @@ pub struct BadgeMessage {
     pub effect: BadgeEffect,
     /// characters as bitmasks (8x11), stuffed together to fill the reports
     pub data: Vec<u8>,
+    /// source text of the message, not sent to the badge
+    pub text: Option<String>,
+    /// font file used to render the source text, not sent to the badge
+    pub font: Option<String>,
 }
 
 impl Default for BadgeMessage {
@@ -148,10 +319,127 @@ impl Default for BadgeMessage {
             speed: 1,
             effect: BadgeEffect::Left,
             data: Vec::with_capacity(MAX_STR * BADGE_MSG_FONT_HEIGHT),
+            text: None,
+            font: None,
+        }
+    }
+}
+
+/// Keywords of the png text chunks holding the message settings
+const PNG_KEYWORD_EFFECT: &str = "badge:effect";
+const PNG_KEYWORD_SPEED: &str = "badge:speed";
+const PNG_KEYWORD_BLINK: &str = "badge:blink";
+const PNG_KEYWORD_FRAME: &str = "badge:frame";
+const PNG_KEYWORD_TEXT: &str = "badge:text";
+const PNG_KEYWORD_FONT: &str = "badge:font";
+
+impl BadgeMessage {
+    /// Message settings to be stored in the png text chunks
+    fn png_text_chunks(&self) -> Vec<(&'static str, String)> {
+        let mut text_chunks = vec![
+            (PNG_KEYWORD_EFFECT, self.effect.to_string()),
+            (PNG_KEYWORD_SPEED, self.speed.to_string()),
+            (PNG_KEYWORD_BLINK, self.blink.to_string()),
+            (PNG_KEYWORD_FRAME, self.frame.to_string()),
+        ];
+        if let Some(text) = &self.text {
+            text_chunks.push((PNG_KEYWORD_TEXT, text.clone()));
+        }
+        if let Some(font) = &self.font {
+            text_chunks.push((PNG_KEYWORD_FONT, font.clone()));
+        }
+        text_chunks
+    }
+
+    /// Apply the message settings read from the png text chunks.
+    ///
+    /// Unknown keywords and wrong values are ignored.
+    fn apply_png_text_chunks(&mut self, text_chunks: &[(String, String)]) {
+        for (keyword, value) in text_chunks {
+            match keyword.as_str() {
+                PNG_KEYWORD_EFFECT => {
+                    if let Ok(effect) = BadgeEffect::from_str(value) {
+                        self.effect = effect;
+                    }
+                }
+                PNG_KEYWORD_SPEED => match u8::from_str(value) {
+                    Ok(speed) if BADGE_SPEED_RANGE.contains(&speed) => self.speed = speed,
+                    _ => (),
+                },
+                PNG_KEYWORD_BLINK => {
+                    if let Ok(blink) = bool::from_str(value) {
+                        self.blink = blink;
+                    }
+                }
+                PNG_KEYWORD_FRAME => {
+                    if let Ok(frame) = bool::from_str(value) {
+                        self.frame = frame;
+                    }
+                }
+                PNG_KEYWORD_TEXT => self.text = Some(value.clone()),
+                PNG_KEYWORD_FONT => self.font = Some(value.clone()),
+                _ => (),
+            }
         }
     }
 }
 
+#[test]
+fn test_badge_message_png_text_chunks() {
+    let message = BadgeMessage {
+        blink: true,
+        speed: 5,
+        effect: BadgeEffect::Laser,
+        text: Some("Hello".to_string()),
+        ..Default::default()
+    };
+    let text_chunks = message
+        .png_text_chunks()
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect::<Vec<_>>();
+    assert_eq!(text_chunks.len(), 5);
+
+    let mut restored = BadgeMessage::default();
+    restored.apply_png_text_chunks(&text_chunks);
+    assert_eq!(
+        (
+            restored.blink,
+            restored.frame,
+            restored.speed,
+            restored.effect
+        ),
+        (true, false, 5, BadgeEffect::Laser)
+    );
+    assert_eq!(restored.text.as_deref(), Some("Hello"));
+    assert_eq!(restored.font, None);
+
+    restored.apply_png_text_chunks(&[
+        (PNG_KEYWORD_SPEED.to_string(), "9".to_string()),
+        (PNG_KEYWORD_EFFECT.to_string(), "unknown".to_string()),
+        ("Comment".to_string(), "ignored".to_string()),
+    ]);
+    assert_eq!((restored.speed, restored.effect), (5, BadgeEffect::Laser));
+}
+
+/// Read png data as a bitmap such as a frame of the animation
+pub fn read_png_frame<R: Read + BufRead + Seek>(
+    reader: R,
+    options: &ImageImportOptions,
+) -> Result<Canvas, BadgeError> {
+    image_io::read_png_to_canvas(reader, options).map_err(|e| BadgeError::PngReadError(None, e))
+}
+
+/// Read the sprite sheet as the bitmaps of the grid cells such as the frames of the animation
+pub fn read_image_grid_frames<R: Read + BufRead + Seek>(
+    reader: R,
+    grid: ImageGrid,
+    options: &ImageImportOptions,
+) -> Result<Vec<Canvas>, BadgeError> {
+    image_io::read_image_grid_to_canvases(reader, grid, options)
+        .map_err(|e| BadgeError::ImageReadError(None, e))
+}
+
 /// Badge context
 pub struct Badge {
     /// badge brightness
@@ -175,6 +463,7 @@ impl Badge {
         msg_num: usize,
         msg: &str,
         font_names: &[&str],
+        options: &TextRenderOptions,
     ) -> Result<(), BadgeError> {
         if msg_num >= N_MESSAGES {
             Err(BadgeError::MessageNumberOutOfRange(msg_num))
@@ -182,36 +471,387 @@ impl Badge {
             Ok(()) // Do nothing
         } else {
             let pixel_height = BADGE_MSG_FONT_HEIGHT;
-            let (font_path, font_index) = font_names
-                .first()
-                .and_then(|&v| {
-                    let path = PathBuf::from(v);
-                    if path.exists() {
-                        Some(Ok((path, 0)))
-                    } else {
-                        None
+            let font = if font_names.first() == Some(&BUILTIN_FONT_NAME) {
+                None
+            } else {
+                font_names
+                    .first()
+                    .and_then(|&v| {
+                        let path = PathBuf::from(v);
+                        if path.exists() {
+                            Some(Ok((path, 0)))
+                        } else {
+                            None
+                        }
+                    })
+                    .unwrap_or_else(|| select_font(font_names, Some(pixel_height)))
+                    // fontconfig has no font
+                    .ok()
+            };
+            let rendered = match font {
+                Some((font_path, font_index)) => {
+                    let font = Some((font_path.as_path(), font_index));
+                    match render_text(msg, pixel_height, font, font_names, options) {
+                        Ok(pixel_data) => Some((pixel_data, font_path.display().to_string())),
+                        // FreeType cannot render with the font
+                        Err(BadgeError::FontLoading(_)) => None,
+                        Err(e) => return Err(e),
                     }
-                })
-                .unwrap_or_else(|| select_font(font_names, Some(pixel_height)))?;
+                }
+                None => None,
+            };
+            let (mut pixel_data, font) = match rendered {
+                Some(rendered) => rendered,
+                None => (
+                    render_text(msg, pixel_height, None, font_names, options)?,
+                    BUILTIN_FONT_NAME.to_string(),
+                ),
+            };
 
-            let mut pixel_data = render_text(msg, pixel_height, font_path.as_ref(), font_index)?;
             mem::swap(&mut self.messages[msg_num].data, &mut pixel_data);
+            self.messages[msg_num].text = Some(msg.to_string());
+            self.messages[msg_num].font = Some(font);
             Ok(())
         }
     }
 
     /// Add Png message
+    ///
+    /// The message settings stored in the png text chunks by [`Badge::write_to_png`] are applied.
     pub fn add_png_message<R: Read + BufRead + Seek>(
         &mut self,
         msg_num: usize,
         reader: R,
+        options: &ImageImportOptions,
     ) -> Result<(), BadgeError> {
         if msg_num >= N_MESSAGES {
             Err(BadgeError::MessageNumberOutOfRange(msg_num))
         } else {
-            let mut pixel_data = image_io::read_png_to_badge_message(reader)
-                .map_err(|e| BadgeError::PngReadError(None, e))?;
-            mem::swap(&mut self.messages[msg_num].data, &mut pixel_data);
+            let (mut pixel_data, text_chunks) =
+                image_io::read_png_to_badge_message(reader, options)
+                    .map_err(|e| BadgeError::PngReadError(None, e))?;
+            let message = &mut self.messages[msg_num];
+            mem::swap(&mut message.data, &mut pixel_data);
+            message.text = None;
+            message.font = None;
+            message.apply_png_text_chunks(&text_chunks);
+            Ok(())
+        }
+    }
+
+    /// Add message read from the image file of png, gif, bmp, pbm/pgm, xbm or svg
+    ///
+    /// The format is detected by the magic bytes. Gif is read as a still image of the first frame.
+    pub fn add_image_message<R: Read + BufRead + Seek>(
+        &mut self,
+        msg_num: usize,
+        mut reader: R,
+        options: &ImageImportOptions,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            return Err(BadgeError::MessageNumberOutOfRange(msg_num));
+        }
+        let signature = reader
+            .fill_buf()
+            .map_err(|e| BadgeError::ImageReadError(None, e.into()))?;
+        if image_io::is_png(signature) {
+            return self.add_png_message(msg_num, reader, options);
+        }
+
+        let canvas = image_io::read_image_to_canvas(reader, options)
+            .map_err(|e| BadgeError::ImageReadError(None, e))?;
+        let message = &mut self.messages[msg_num];
+        message.data = canvas2vec(&canvas);
+        message.text = None;
+        message.font = None;
+        Ok(())
+    }
+
+    /// Add message drawn as the ASCII art of `#` (lit) and `.` (unlit)
+    ///
+    /// The rows are separated by the newlines or `/` such as `.#./#.#/.#.`.
+    pub fn add_ascii_art_message(&mut self, msg_num: usize, art: &str) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            return Err(BadgeError::MessageNumberOutOfRange(msg_num));
+        }
+        let canvas =
+            text_bitmap::read_ascii_art(art).map_err(|e| BadgeError::ImageReadError(None, e))?;
+        self.set_message_data(msg_num, canvas2vec(&canvas));
+        Ok(())
+    }
+
+    /// Add message of the hex values of the columns; `0x400` is the top row of a column.
+    pub fn add_hex_columns_message(
+        &mut self,
+        msg_num: usize,
+        columns: &str,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            return Err(BadgeError::MessageNumberOutOfRange(msg_num));
+        }
+        let canvas = text_bitmap::read_hex_columns(columns)
+            .map_err(|e| BadgeError::ImageReadError(None, e))?;
+        self.set_message_data(msg_num, canvas2vec(&canvas));
+        Ok(())
+    }
+
+    /// Add message read from the raw bytes in the wire layout, i.e. 11 bytes per 8 columns
+    ///
+    /// The width of the message in columns can be given explicitly to check the data length.
+    pub fn add_raw_message<R: Read>(
+        &mut self,
+        msg_num: usize,
+        mut reader: R,
+        width: Option<usize>,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            return Err(BadgeError::MessageNumberOutOfRange(msg_num));
+        }
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .map_err(|e| BadgeError::ImageReadError(None, e.into()))?;
+        let data = image_io::read_raw_badge_message(&data, width)
+            .map_err(|e| BadgeError::ImageReadError(None, e))?;
+        self.set_message_data(msg_num, data);
+        Ok(())
+    }
+
+    /// Add message read from the hex text of the bytes in the wire layout
+    ///
+    /// See [`Badge::add_raw_message`] for the layout.
+    pub fn add_hex_message<R: Read>(
+        &mut self,
+        msg_num: usize,
+        mut reader: R,
+        width: Option<usize>,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            return Err(BadgeError::MessageNumberOutOfRange(msg_num));
+        }
+        let mut text = String::new();
+        reader
+            .read_to_string(&mut text)
+            .map_err(|e| BadgeError::ImageReadError(None, e.into()))?;
+        let data = image_io::read_hex_badge_message(&text, width)
+            .map_err(|e| BadgeError::ImageReadError(None, e))?;
+        self.set_message_data(msg_num, data);
+        Ok(())
+    }
+
+    /// Replace the bitmap of the message which is no longer rendered from text
+    fn set_message_data(&mut self, msg_num: usize, data: Vec<u8>) {
+        let message = &mut self.messages[msg_num];
+        message.data = data;
+        message.text = None;
+        message.font = None;
+    }
+
+    /// Add messages of the sprite sheet cells to the consecutive slots starting at `msg_num`
+    ///
+    /// The cells are taken in row-major order. Returns the number of the slots used.
+    pub fn add_image_grid_messages<R: Read + BufRead + Seek>(
+        &mut self,
+        msg_num: usize,
+        reader: R,
+        grid: ImageGrid,
+        options: &ImageImportOptions,
+    ) -> Result<usize, BadgeError> {
+        if msg_num >= N_MESSAGES {
+            return Err(BadgeError::MessageNumberOutOfRange(msg_num));
+        } else if grid
+            .columns
+            .checked_mul(grid.rows)
+            .is_none_or(|cells| cells > N_MESSAGES - msg_num)
+        {
+            return Err(BadgeError::TooManyGridCells(
+                grid.columns,
+                grid.rows,
+                msg_num,
+            ));
+        }
+        let cells = read_image_grid_frames(reader, grid, options)?
+            .iter()
+            .map(canvas2vec)
+            .collect::<Vec<_>>();
+        self.check_device_memory_replaced(msg_num, &cells)?;
+        let count = cells.len();
+        for (message, data) in self.messages[msg_num..].iter_mut().zip(cells) {
+            message.data = data;
+            message.text = None;
+            message.font = None;
+        }
+        Ok(count)
+    }
+
+    /// Split the message into the consecutive slots starting at `msg_num`
+    ///
+    /// Each slot holds at most `max_width` columns. If `at_blank` is true, the message is cut at
+    /// the blank columns, e.g. between words, where possible.
+    /// The effect, speed, blink and frame of the message are shared by all the slots.
+    /// Returns the number of the slots used.
+    pub fn split_message(
+        &mut self,
+        msg_num: usize,
+        max_width: usize,
+        at_blank: bool,
+    ) -> Result<usize, BadgeError> {
+        if msg_num >= N_MESSAGES {
+            return Err(BadgeError::MessageNumberOutOfRange(msg_num));
+        }
+        let message = &self.messages[msg_num];
+        let canvas = vec2canvas(&message.data, BADGE_MSG_FONT_HEIGHT);
+        let pieces = canvas.split_columns(max_width, at_blank);
+        if msg_num + pieces.len() > N_MESSAGES {
+            return Err(BadgeError::TooManySlots(msg_num, pieces.len()));
+        }
+
+        let slices = pieces
+            .into_iter()
+            .map(|columns| {
+                let mut slice = Canvas::new(columns.len(), BADGE_MSG_FONT_HEIGHT);
+                slice.blit_columns(&canvas, columns, 0);
+                canvas2vec(&slice)
+            })
+            .collect::<Vec<_>>();
+        self.check_device_memory_replaced(msg_num, &slices)?;
+
+        let count = slices.len();
+        let (blink, frame, speed, effect) =
+            (message.blink, message.frame, message.speed, message.effect);
+        for (i, data) in slices.into_iter().enumerate() {
+            self.messages[msg_num + i] = BadgeMessage {
+                blink,
+                frame,
+                speed,
+                effect,
+                data,
+                text: None,
+                font: None,
+            };
+        }
+        Ok(count)
+    }
+
+    /// Check that the data of all the messages fit in the display memory of the device
+    pub fn check_device_memory(&self) -> Result<(), BadgeError> {
+        self.check_device_memory_replaced(0, &[])
+    }
+
+    /// Check the display memory as if the slots from `msg_num` held `data`,
+    /// so that the messages are not modified if they would not fit.
+    fn check_device_memory_replaced(
+        &self,
+        msg_num: usize,
+        data: &[Vec<u8>],
+    ) -> Result<(), BadgeError> {
+        let size = self
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(i, message)| {
+                i.checked_sub(msg_num)
+                    .and_then(|j| data.get(j))
+                    .unwrap_or(&message.data)
+                    .len()
+            })
+            .sum();
+        if size > DISP_SIZE {
+            Err(BadgeError::DeviceMemoryExceeded(size, DISP_SIZE))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Add animation message built from frames
+    ///
+    /// Each frame must be as high as the message and must not light up any column beyond
+    /// `frame_width`, which is usually the visible width of the device.
+    /// Frames are left-aligned and padded to the byte boundary of `frame_width`,
+    /// and the effect of the message is set to [`BadgeEffect::Animation`].
+    pub fn add_frames_message(
+        &mut self,
+        msg_num: usize,
+        frames: &[Canvas],
+        frame_width: usize,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            return Err(BadgeError::MessageNumberOutOfRange(msg_num));
+        } else if frames.is_empty() {
+            return Ok(()); // Do nothing
+        }
+
+        let frame_stride = frame_width.div_ceil(8) * 8;
+        if let Some((i, frame)) = frames.iter().enumerate().find(|(_, frame)| {
+            frame.height != BADGE_MSG_FONT_HEIGHT
+                || frame.width > frame_stride
+                || frame
+                    .content_columns()
+                    .is_some_and(|columns| columns.end > frame_width)
+        }) {
+            return Err(BadgeError::WrongFrameSize(i, frame.width, frame.height));
+        }
+
+        let mut animation = Canvas::new(frame_stride * frames.len(), BADGE_MSG_FONT_HEIGHT);
+        for (i, frame) in frames.iter().enumerate() {
+            animation.blit_columns(frame, 0..frame.width, i * frame_stride);
+        }
+        let message = &mut self.messages[msg_num];
+        message.data = canvas2vec(&animation);
+        message.effect = BadgeEffect::Animation;
+        message.text = None;
+        message.font = None;
+        Ok(())
+    }
+
+    /// Add animation message read from animated gif or png
+    ///
+    /// Each frame is scaled to fit in the message height and `frame_width` columns,
+    /// which is usually the visible width of the device.
+    /// The frames are converted with `options` in the same way as [`Badge::add_image_message`].
+    pub fn add_animation_message<R: Read + BufRead + Seek>(
+        &mut self,
+        msg_num: usize,
+        reader: R,
+        frame_width: usize,
+        options: &ImageImportOptions,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            Err(BadgeError::MessageNumberOutOfRange(msg_num))
+        } else {
+            let frames = image_io::read_animation_to_canvases(reader, frame_width, options)
+                .map_err(|e| BadgeError::ImageReadError(None, e))?;
+            self.add_frames_message(msg_num, &frames, frame_width)
+        }
+    }
+
+    /// Align the message data horizontally within `width` columns
+    ///
+    /// Blank columns around the message are trimmed before the alignment.
+    /// A message wider than `width` is just trimmed.
+    pub fn align_message(
+        &mut self,
+        msg_num: usize,
+        alignment: BadgeAlignment,
+        width: usize,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            Err(BadgeError::MessageNumberOutOfRange(msg_num))
+        } else if self.messages[msg_num].data.is_empty() {
+            Ok(()) // Do nothing
+        } else {
+            let canvas = vec2canvas(&self.messages[msg_num].data, BADGE_MSG_FONT_HEIGHT);
+            let columns = canvas.content_columns().unwrap_or(0..0);
+            let aligned_width = width.max(columns.len());
+            let x = match alignment {
+                BadgeAlignment::Left => 0,
+                BadgeAlignment::Center => (aligned_width - columns.len()) / 2,
+                BadgeAlignment::Right => aligned_width - columns.len(),
+            };
+
+            let mut aligned = Canvas::new(aligned_width, BADGE_MSG_FONT_HEIGHT);
+            aligned.blit_columns(&canvas, columns, x);
+            self.messages[msg_num].data = canvas2vec(&aligned);
             Ok(())
         }
     }
@@ -278,6 +918,7 @@ impl Badge {
     ///
     /// If failed to write the data to the device, then an error is returned.\
     pub fn send(&mut self, badge_type: BadgeType) -> Result<(), BadgeError> {
+        self.check_device_memory()?;
         device::device_send(badge_type, self)
     }
 
@@ -288,11 +929,161 @@ impl Badge {
         } else if self.messages[msg_num].data.is_empty() {
             Err(BadgeError::NoDataToWrite)
         } else {
-            let message_data = self.messages[msg_num].data.as_slice();
-            image_io::write_badge_message_to_png(message_data, writer)
+            let message = &self.messages[msg_num];
+            image_io::write_badge_message_to_png(&message.data, &message.png_text_chunks(), writer)
                 .map_err(|e| BadgeError::PngWriteError(None, e))
         }
     }
+
+    /// Write the message bitmap in `format`
+    pub fn write_message<W: Write>(
+        &self,
+        msg_num: usize,
+        format: MessageFormat,
+        writer: W,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            return Err(BadgeError::MessageNumberOutOfRange(msg_num));
+        } else if self.messages[msg_num].data.is_empty() {
+            return Err(BadgeError::NoDataToWrite);
+        }
+        let message_data = self.messages[msg_num].data.as_slice();
+        match format {
+            MessageFormat::Png => return self.write_to_png(msg_num, writer),
+            MessageFormat::Pbm => image_io::write_badge_message_to_pbm(message_data, writer),
+            MessageFormat::Xbm => image_io::write_badge_message_to_xbm(message_data, writer),
+            MessageFormat::C => image_io::write_badge_message_to_c(message_data, writer),
+            MessageFormat::Rust => image_io::write_badge_message_to_rust(message_data, writer),
+            MessageFormat::Json => image_io::write_badge_message_to_json(message_data, writer),
+            MessageFormat::Raw => image_io::write_badge_message_to_raw(message_data, writer),
+            MessageFormat::Hex => image_io::write_badge_message_to_hex(message_data, writer),
+        }
+        .map_err(|e| BadgeError::ImageWriteError(None, e))
+    }
+
+    /// Write the message bitmap as text drawn with Unicode characters
+    pub fn write_preview<W: Write>(
+        &self,
+        msg_num: usize,
+        style: PreviewStyle,
+        writer: W,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            Err(BadgeError::MessageNumberOutOfRange(msg_num))
+        } else if self.messages[msg_num].data.is_empty() {
+            Err(BadgeError::NoDataToWrite)
+        } else {
+            let canvas = vec2canvas(&self.messages[msg_num].data, BADGE_MSG_FONT_HEIGHT);
+            terminal::write_preview(&canvas, style, writer).map_err(|e| BadgeError::FileIo(None, e))
+        }
+    }
+
+    /// Write the message bitmap as LED-look image file
+    pub fn write_led_image<W: Write>(
+        &self,
+        msg_num: usize,
+        format: LedImageFormat,
+        options: &LedRenderOptions,
+        writer: W,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            Err(BadgeError::MessageNumberOutOfRange(msg_num))
+        } else if self.messages[msg_num].data.is_empty() {
+            Err(BadgeError::NoDataToWrite)
+        } else {
+            let canvas = vec2canvas(&self.messages[msg_num].data, BADGE_MSG_FONT_HEIGHT);
+            match format {
+                LedImageFormat::Png => {
+                    image_io::write_rgba_to_png(&led_render::render_leds(&canvas, options), writer)
+                }
+                LedImageFormat::Svg => led_render::write_leds_to_svg(&canvas, options, writer)
+                    .map_err(image_io::BadgeImageWriteError::from),
+            }
+            .map_err(|e| BadgeError::ImageWriteError(None, e))
+        }
+    }
+
+    /// Write all non-empty messages into a png sprite sheet and its json description
+    pub fn write_sprite_sheet<W: Write, J: Write>(
+        &self,
+        png_writer: W,
+        json_writer: J,
+    ) -> Result<(), BadgeError> {
+        if self.messages.iter().all(|v| v.data.is_empty()) {
+            return Err(BadgeError::NoDataToWrite);
+        }
+        sprite_sheet::write_sprite_sheet_png(self, png_writer)
+            .map_err(|e| BadgeError::ImageWriteError(None, e))?;
+        sprite_sheet::write_sprite_sheet_json(self, json_writer)
+            .map_err(|e| BadgeError::FileIo(None, e))
+    }
+
+    /// Write the html page which plays the messages in the browser
+    pub fn write_html_preview<W: Write>(&self, width: usize, writer: W) -> Result<(), BadgeError> {
+        if self.messages.iter().all(|v| v.data.is_empty()) {
+            return Err(BadgeError::NoDataToWrite);
+        }
+        html::write_html_preview(self, width, writer).map_err(|e| BadgeError::FileIo(None, e))
+    }
+
+    /// Write the message bitmap as LED-look image with the terminal graphics protocol
+    pub fn write_graphics_preview<W: Write>(
+        &self,
+        msg_num: usize,
+        protocol: GraphicsProtocol,
+        options: &LedRenderOptions,
+        writer: W,
+    ) -> Result<(), BadgeError> {
+        if msg_num >= N_MESSAGES {
+            Err(BadgeError::MessageNumberOutOfRange(msg_num))
+        } else if self.messages[msg_num].data.is_empty() {
+            Err(BadgeError::NoDataToWrite)
+        } else {
+            let canvas = vec2canvas(&self.messages[msg_num].data, BADGE_MSG_FONT_HEIGHT);
+            terminal::write_graphics_preview(&canvas, protocol, options, writer)
+                .map_err(|e| BadgeError::ImageWriteError(None, e))
+        }
+    }
+
+    /// Play the simulated display of the badge of `width` columns on the terminal
+    ///
+    /// The frames are drawn in place with ANSI cursor movement every `time_step` milliseconds.
+    pub fn play_preview<W: Write>(
+        &self,
+        width: usize,
+        time_step: u32,
+        style: PreviewStyle,
+        writer: W,
+    ) -> Result<(), BadgeError> {
+        let frames = simulator::simulate_badge(self, width, BADGE_MSG_FONT_HEIGHT, time_step);
+        if frames.is_empty() {
+            return Err(BadgeError::NoDataToWrite);
+        }
+        let delay = Duration::from_millis(time_step as u64);
+        terminal::play_preview(&frames, delay, style, writer)
+            .map_err(|e| BadgeError::FileIo(None, e))
+    }
+
+    /// Write animation simulating how the badge of `width` columns displays the messages
+    ///
+    /// The display is sampled every `time_step` milliseconds.
+    pub fn write_simulation<W: Write>(
+        &self,
+        width: usize,
+        time_step: u32,
+        format: AnimationFormat,
+        writer: W,
+    ) -> Result<(), BadgeError> {
+        let frames = simulator::simulate_badge(self, width, BADGE_MSG_FONT_HEIGHT, time_step);
+        if frames.is_empty() {
+            return Err(BadgeError::NoDataToWrite);
+        }
+        match format {
+            AnimationFormat::Gif => image_io::write_animation_to_gif(&frames, time_step, writer),
+            AnimationFormat::Png => image_io::write_animation_to_png(&frames, time_step, writer),
+        }
+        .map_err(|e| BadgeError::ImageWriteError(None, e))
+    }
 }
 
 #[test]
@@ -303,51 +1094,384 @@ fn test_badge_new() {
 #[test]
 fn test_add_png_message() {
     let mut badge = Badge::new().unwrap();
+    let options = ImageImportOptions::default();
 
     let sample_data: [u8; BADGE_MSG_FONT_HEIGHT] = [0xff; BADGE_MSG_FONT_HEIGHT];
     let mut generated_png_data = Vec::<u8>::new();
     {
         let mut w = Cursor::new(&mut generated_png_data);
-        image_io::write_badge_message_to_png(&sample_data, w.get_mut()).unwrap();
+        image_io::write_badge_message_to_png(&sample_data, &[], w.get_mut()).unwrap();
     }
 
     let corrupted_data = vec![0; 1];
 
     let reader = Cursor::new(&generated_png_data);
     assert!(matches!(
-        badge.add_png_message(N_MESSAGES, reader),
+        badge.add_png_message(N_MESSAGES, reader, &options),
         Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
     ));
 
     let reader = Cursor::new(&corrupted_data);
     assert!(matches!(
-        badge.add_png_message(N_MESSAGES - 1, reader),
+        badge.add_png_message(N_MESSAGES - 1, reader, &options),
         Err(BadgeError::PngReadError(None, _))
     ));
 
     let reader = Cursor::new(&generated_png_data);
-    assert!(badge.add_png_message(N_MESSAGES - 1, reader).is_ok());
+    assert!(
+        badge
+            .add_png_message(N_MESSAGES - 1, reader, &options)
+            .is_ok()
+    );
     assert_eq!(badge.messages[N_MESSAGES - 1].data, &sample_data);
+    assert_eq!(badge.messages[N_MESSAGES - 1].effect, BadgeEffect::Left);
+
+    // settings round trip through the png text chunks
+    badge.messages[0].data = sample_data.to_vec();
+    badge.messages[0].effect = BadgeEffect::Volume;
+    badge.messages[0].speed = 6;
+    badge.messages[0].frame = true;
+    badge.messages[0].text = Some("Grüße".to_string());
+    let mut png_data = Vec::<u8>::new();
+    badge.write_to_png(0, &mut png_data).unwrap();
+    assert!(
+        badge
+            .add_png_message(1, Cursor::new(&png_data), &options)
+            .is_ok()
+    );
+    let message = &badge.messages[1];
+    assert_eq!(message.data, &sample_data);
+    assert_eq!(
+        (message.effect, message.speed, message.blink, message.frame),
+        (BadgeEffect::Volume, 6, false, true)
+    );
+    assert_eq!(message.text.as_deref(), Some("Grüße"));
+}
+
+#[test]
+fn test_add_image_message() {
+    let mut badge = Badge::new().unwrap();
+    let options = ImageImportOptions::default();
+
+    let mut xbm = b"#define a_width 8\n#define a_height 11\nstatic char a_bits[] = {".to_vec();
+    xbm.extend(b"0x81,".repeat(BADGE_MSG_FONT_HEIGHT));
+    xbm.extend(b"};");
+    assert!(matches!(
+        badge.add_image_message(N_MESSAGES, Cursor::new(&xbm), &options),
+        Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
+    ));
+    assert!(
+        badge
+            .add_image_message(0, Cursor::new(&xbm), &options)
+            .is_ok()
+    );
+    assert_eq!(
+        badge.messages[0].data,
+        vec![0b1000_0001; BADGE_MSG_FONT_HEIGHT]
+    );
+
+    badge.messages[0].effect = BadgeEffect::Snow;
+    let mut png_data = Vec::<u8>::new();
+    badge.write_to_png(0, &mut png_data).unwrap();
+    assert!(
+        badge
+            .add_image_message(1, Cursor::new(&png_data), &options)
+            .is_ok()
+    );
+    assert_eq!(badge.messages[1].effect, BadgeEffect::Snow);
+
+    assert!(matches!(
+        badge.add_image_message(2, Cursor::new(b"P5 1 1 255\n"), &options),
+        Err(BadgeError::ImageReadError(None, _))
+    ));
 }
 
 #[test]
 fn test_badge_add_text_message() {
     let mut badge = Badge::new().unwrap();
     let font_names = &["Liberation Sans", "Arial"];
+    let options = TextRenderOptions::default();
 
     assert!(matches!(
-        badge.add_text_message(N_MESSAGES, "", font_names),
+        badge.add_text_message(N_MESSAGES, "", font_names, &options),
         Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
     ));
 
     assert!(matches!(
-        badge.add_text_message(N_MESSAGES - 1, "", font_names),
+        badge.add_text_message(N_MESSAGES - 1, "", font_names, &options),
         Ok(())
     ));
     assert!(badge.messages[N_MESSAGES - 1].data.iter().all(|&v| v == 0));
 
-    assert!(matches!(badge.add_text_message(0, "A", font_names), Ok(())));
+    assert!(matches!(
+        badge.add_text_message(0, "A", font_names, &options),
+        Ok(())
+    ));
     assert!(badge.messages[0].data.iter().any(|&v| v != 0));
+
+    assert!(matches!(
+        badge.add_text_message(1, "AB", &[BUILTIN_FONT_NAME], &options),
+        Ok(())
+    ));
+    assert_eq!(badge.messages[1].data.len(), 2 * BADGE_MSG_FONT_HEIGHT);
+    assert_eq!(badge.messages[1].font.as_deref(), Some(BUILTIN_FONT_NAME));
+}
+
+#[test]
+fn test_badge_add_frames_message() {
+    let mut badge = Badge::new().unwrap();
+
+    let mut frame1 = Canvas::new(44, BADGE_MSG_FONT_HEIGHT);
+    frame1.set(0, 0, true);
+    let mut frame2 = Canvas::new(10, BADGE_MSG_FONT_HEIGHT);
+    frame2.set(9, 10, true);
+    let frames = [frame1, frame2];
+
+    assert!(matches!(
+        badge.add_frames_message(N_MESSAGES, &frames, 44),
+        Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
+    ));
+    assert!(matches!(
+        badge.add_frames_message(0, &frames, 40),
+        Err(BadgeError::WrongFrameSize(0, 44, BADGE_MSG_FONT_HEIGHT))
+    ));
+    assert!(matches!(
+        badge.add_frames_message(0, &frames[1..], 9),
+        Err(BadgeError::WrongFrameSize(0, 10, BADGE_MSG_FONT_HEIGHT))
+    ));
+    assert!(matches!(
+        badge.add_frames_message(0, &[Canvas::new(8, 7)], 44),
+        Err(BadgeError::WrongFrameSize(0, 8, 7))
+    ));
+
+    assert!(badge.add_frames_message(0, &frames, 44).is_ok());
+    assert_eq!(badge.messages[0].effect, BadgeEffect::Animation);
+    let canvas = vec2canvas(&badge.messages[0].data, BADGE_MSG_FONT_HEIGHT);
+    assert_eq!(canvas.width, 2 * 48);
+    assert!(canvas.get(0, 0));
+    assert!(canvas.get(48 + 9, 10));
+    assert_eq!(canvas.pixels.iter().filter(|&&v| v != 0).count(), 2);
+}
+
+#[test]
+fn test_badge_add_image_grid_messages() {
+    let mut badge = Badge::new().unwrap();
+    let options = ImageImportOptions::default();
+    let mut pbm = b"P1 2 22\n".to_vec();
+    pbm.extend(b"10\n".repeat(BADGE_MSG_FONT_HEIGHT));
+    pbm.extend(b"01\n".repeat(BADGE_MSG_FONT_HEIGHT));
+    let grid = ImageGrid {
+        columns: 2,
+        rows: 2,
+    };
+
+    assert!(matches!(
+        badge.add_image_grid_messages(6, Cursor::new(&pbm), grid, &options),
+        Err(BadgeError::TooManyGridCells(2, 2, 6))
+    ));
+    let huge_grid = ImageGrid {
+        columns: usize::MAX,
+        rows: 2,
+    };
+    assert!(matches!(
+        badge.add_image_grid_messages(0, Cursor::new(&pbm), huge_grid, &options),
+        Err(BadgeError::TooManyGridCells(usize::MAX, 2, 0))
+    ));
+
+    // the slots are kept if the cells do not fit in the device memory
+    badge.messages[0].data = vec![0xFF; DISP_SIZE - BADGE_MSG_FONT_HEIGHT];
+    badge.messages[2].text = Some("kept".to_string());
+    assert!(matches!(
+        badge.add_image_grid_messages(1, Cursor::new(&pbm), grid, &options),
+        Err(BadgeError::DeviceMemoryExceeded(_, DISP_SIZE))
+    ));
+    assert_eq!(badge.messages[2].text.as_deref(), Some("kept"));
+    badge.messages[0].data.clear();
+
+    assert_eq!(
+        badge
+            .add_image_grid_messages(1, Cursor::new(&pbm), grid, &options)
+            .unwrap(),
+        4
+    );
+    let lit = |i: usize| badge.messages[i].data.iter().any(|&v| v != 0);
+    assert_eq!(
+        (1..5).map(lit).collect::<Vec<_>>(),
+        [true, false, false, true]
+    );
+}
+
+#[test]
+fn test_badge_add_raw_message() {
+    let mut badge = Badge::new().unwrap();
+    let data = [0x80; BADGE_MSG_FONT_HEIGHT];
+    assert!(matches!(
+        badge.add_raw_message(N_MESSAGES, Cursor::new(&data), None),
+        Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
+    ));
+    badge
+        .add_raw_message(0, Cursor::new(&data), Some(1))
+        .unwrap();
+    assert_eq!(badge.messages[0].data, data);
+
+    let mut hex = Vec::new();
+    badge
+        .write_message(0, MessageFormat::Hex, &mut hex)
+        .unwrap();
+    badge.add_hex_message(1, Cursor::new(&hex), None).unwrap();
+    assert_eq!(badge.messages[1].data, data);
+    assert!(matches!(
+        badge.add_hex_message(1, Cursor::new("80 8"), None),
+        Err(BadgeError::ImageReadError(None, _))
+    ));
+}
+
+#[test]
+fn test_badge_add_ascii_art_and_hex_columns_message() {
+    let mut badge = Badge::new().unwrap();
+    assert!(matches!(
+        badge.add_ascii_art_message(N_MESSAGES, "#"),
+        Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
+    ));
+    badge.add_ascii_art_message(0, "#.#").unwrap();
+    badge.add_hex_columns_message(1, "20 0 20").unwrap();
+    assert_eq!(badge.messages[0].data, badge.messages[1].data);
+    assert_eq!(badge.messages[0].data[5], 0b1010_0000);
+    assert!(matches!(
+        badge.add_hex_columns_message(1, "#"),
+        Err(BadgeError::ImageReadError(None, _))
+    ));
+}
+
+#[test]
+fn test_badge_split_message() {
+    let mut badge = Badge::new().unwrap();
+    assert!(matches!(
+        badge.split_message(N_MESSAGES, 8, false),
+        Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
+    ));
+
+    // 20 lit columns
+    let mut canvas = Canvas::new(24, BADGE_MSG_FONT_HEIGHT);
+    for x in 0..20 {
+        canvas.set(x, 0, true);
+    }
+    badge.messages[1].data = canvas2vec(&canvas);
+    badge.messages[1].effect = BadgeEffect::Freeze;
+    badge.messages[1].speed = 5;
+    badge.messages[1].text = Some("text".to_string());
+    assert_eq!(badge.split_message(1, 8, false).unwrap(), 3);
+    for (i, width) in [(1, 8), (2, 8), (3, 4)] {
+        let message = &badge.messages[i];
+        let canvas = vec2canvas(&message.data, BADGE_MSG_FONT_HEIGHT);
+        assert_eq!(canvas.width, 8);
+        assert_eq!(canvas.content_columns(), Some(0..width));
+        assert_eq!(message.effect, BadgeEffect::Freeze);
+        assert_eq!(message.speed, 5);
+        assert_eq!(message.text, None);
+    }
+
+    badge.messages[6].data = canvas2vec(&canvas);
+    assert!(matches!(
+        badge.split_message(6, 8, false),
+        Err(BadgeError::TooManySlots(6, 3))
+    ));
+
+    // the slots are kept if the pieces do not fit in the device memory
+    let mut badge = Badge::new().unwrap();
+    badge.messages[0].data = canvas2vec(&canvas);
+    badge.messages[2].text = Some("kept".to_string());
+    badge.messages[7].data = vec![0xFF; DISP_SIZE - 3 * BADGE_MSG_FONT_HEIGHT];
+    assert!(matches!(
+        badge.split_message(0, 4, false),
+        Err(BadgeError::DeviceMemoryExceeded(_, DISP_SIZE))
+    ));
+    assert_eq!(badge.messages[0].data, canvas2vec(&canvas));
+    assert_eq!(badge.messages[2].text.as_deref(), Some("kept"));
+
+    badge.messages[0].data = vec![0xFF; DISP_SIZE];
+    assert!(matches!(
+        badge.check_device_memory(),
+        Err(BadgeError::DeviceMemoryExceeded(_, DISP_SIZE))
+    ));
+}
+
+#[test]
+fn test_badge_add_animation_message() {
+    let mut badge = Badge::new().unwrap();
+
+    let mut gif_data = Vec::new();
+    {
+        let palette = [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF];
+        let mut encoder = gif::Encoder::new(&mut gif_data, 4, 1, &palette).unwrap();
+        for _ in 0..3 {
+            let frame = gif::Frame {
+                width: 4,
+                height: 1,
+                buffer: std::borrow::Cow::Borrowed(&[1, 1, 1, 1]),
+                ..Default::default()
+            };
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+
+    assert!(matches!(
+        badge.add_animation_message(N_MESSAGES, Cursor::new(&gif_data), 44, &Default::default()),
+        Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
+    ));
+    assert!(matches!(
+        badge.add_animation_message(0, Cursor::new(&[0u8; 4]), 44, &Default::default()),
+        Err(BadgeError::ImageReadError(None, _))
+    ));
+
+    assert!(
+        badge
+            .add_animation_message(0, Cursor::new(&gif_data), 44, &Default::default())
+            .is_ok()
+    );
+    assert_eq!(badge.messages[0].effect, BadgeEffect::Animation);
+    assert_eq!(badge.messages[0].data.len(), 3 * 6 * BADGE_MSG_FONT_HEIGHT);
+}
+
+#[test]
+fn test_badge_align_message() {
+    let mut badge = Badge::new().unwrap();
+
+    assert!(matches!(
+        badge.align_message(N_MESSAGES, BadgeAlignment::Center, 44),
+        Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
+    ));
+
+    assert!(badge.align_message(0, BadgeAlignment::Center, 44).is_ok());
+    assert!(badge.messages[0].data.is_empty());
+
+    // 4 lit columns at x = 10..14
+    let mut canvas = Canvas::new(24, BADGE_MSG_FONT_HEIGHT);
+    for x in 10..14 {
+        canvas.set(x, 0, true);
+    }
+    let data = canvas2vec(&canvas);
+
+    let aligned_columns = |badge: &Badge| {
+        let canvas = vec2canvas(&badge.messages[0].data, BADGE_MSG_FONT_HEIGHT);
+        (canvas.width, canvas.content_columns().unwrap())
+    };
+
+    badge.messages[0].data = data.clone();
+    badge.align_message(0, BadgeAlignment::Left, 44).unwrap();
+    assert_eq!(aligned_columns(&badge), (48, 0..4));
+
+    badge.messages[0].data = data.clone();
+    badge.align_message(0, BadgeAlignment::Center, 44).unwrap();
+    assert_eq!(aligned_columns(&badge), (48, 20..24));
+
+    badge.messages[0].data = data.clone();
+    badge.align_message(0, BadgeAlignment::Right, 48).unwrap();
+    assert_eq!(aligned_columns(&badge), (48, 44..48));
+
+    badge.messages[0].data = data.clone();
+    badge.align_message(0, BadgeAlignment::Right, 2).unwrap();
+    assert_eq!(aligned_columns(&badge), (8, 0..4));
 }
 
 #[test]
@@ -466,3 +1590,193 @@ fn test_write_to_png() {
         &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]
     );
 }
+
+#[test]
+fn test_write_preview() {
+    let mut badge = Badge::new().unwrap();
+
+    let mut text = Vec::<u8>::new();
+    assert!(matches!(
+        badge.write_preview(N_MESSAGES, PreviewStyle::HalfBlock, &mut text),
+        Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
+    ));
+    assert!(matches!(
+        badge.write_preview(0, PreviewStyle::HalfBlock, &mut text),
+        Err(BadgeError::NoDataToWrite)
+    ));
+
+    badge.messages[0].data = vec![0xFF; BADGE_MSG_FONT_HEIGHT];
+    let mut text = Vec::<u8>::new();
+    assert!(
+        badge
+            .write_preview(0, PreviewStyle::HalfBlock, &mut text)
+            .is_ok()
+    );
+    let text = String::from_utf8(text).unwrap();
+    assert_eq!(text.lines().count(), BADGE_MSG_FONT_HEIGHT.div_ceil(2));
+    assert_eq!(text.lines().next().unwrap(), "\u{2588}".repeat(8));
+
+    let mut text = Vec::<u8>::new();
+    assert!(
+        badge
+            .write_preview(0, PreviewStyle::Braille, &mut text)
+            .is_ok()
+    );
+    let text = String::from_utf8(text).unwrap();
+    assert_eq!(text.lines().count(), BADGE_MSG_FONT_HEIGHT.div_ceil(4));
+    assert_eq!(text.lines().next().unwrap(), "\u{28FF}".repeat(4));
+}
+
+#[test]
+fn test_write_message() {
+    let mut badge = Badge::new().unwrap();
+    let mut data = Vec::<u8>::new();
+    assert!(matches!(
+        badge.write_message(0, MessageFormat::Pbm, &mut data),
+        Err(BadgeError::NoDataToWrite)
+    ));
+
+    badge.messages[0].data = vec![0xAA; BADGE_MSG_FONT_HEIGHT];
+    for format in MessageFormat::values() {
+        let mut data = Vec::<u8>::new();
+        assert!(
+            badge.write_message(0, format, &mut data).is_ok(),
+            "{}",
+            format
+        );
+        assert!(!data.is_empty(), "{}", format);
+    }
+}
+
+#[test]
+fn test_write_led_image() {
+    let mut badge = Badge::new().unwrap();
+    let options = LedRenderOptions::default();
+
+    let mut data = Vec::<u8>::new();
+    assert!(matches!(
+        badge.write_led_image(0, LedImageFormat::Png, &options, &mut data),
+        Err(BadgeError::NoDataToWrite)
+    ));
+
+    badge.messages[0].data = vec![0xAA; BADGE_MSG_FONT_HEIGHT];
+    let mut data = Vec::<u8>::new();
+    assert!(
+        badge
+            .write_led_image(0, LedImageFormat::Png, &options, &mut data)
+            .is_ok()
+    );
+    assert_eq!(
+        &data[0..8],
+        &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]
+    );
+
+    let mut data = Vec::<u8>::new();
+    assert!(
+        badge
+            .write_led_image(0, LedImageFormat::Svg, &options, &mut data)
+            .is_ok()
+    );
+    assert!(data.starts_with(b"<svg "));
+}
+
+#[test]
+fn test_write_sprite_sheet() {
+    let mut badge = Badge::new().unwrap();
+    let (mut png_data, mut json_data) = (Vec::<u8>::new(), Vec::<u8>::new());
+    assert!(matches!(
+        badge.write_sprite_sheet(&mut png_data, &mut json_data),
+        Err(BadgeError::NoDataToWrite)
+    ));
+
+    badge.messages[3].data = vec![0xFF; BADGE_MSG_FONT_HEIGHT];
+    assert!(
+        badge
+            .write_sprite_sheet(&mut png_data, &mut json_data)
+            .is_ok()
+    );
+    assert_eq!(
+        &png_data[0..8],
+        &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]
+    );
+    assert!(json_data.starts_with(b"{"));
+}
+
+#[test]
+fn test_badge_write_html_preview() {
+    let mut badge = Badge::new().unwrap();
+    let mut data = Vec::<u8>::new();
+    assert!(matches!(
+        badge.write_html_preview(44, &mut data),
+        Err(BadgeError::NoDataToWrite)
+    ));
+
+    badge.messages[2].data = vec![0xFF; BADGE_MSG_FONT_HEIGHT];
+    let mut data = Vec::<u8>::new();
+    assert!(badge.write_html_preview(44, &mut data).is_ok());
+    assert!(data.starts_with(b"<!DOCTYPE html>"));
+}
+
+#[test]
+fn test_write_graphics_preview() {
+    let mut badge = Badge::new().unwrap();
+    let options = LedRenderOptions::default();
+
+    let mut output = Vec::<u8>::new();
+    assert!(matches!(
+        badge.write_graphics_preview(N_MESSAGES, GraphicsProtocol::Sixel, &options, &mut output),
+        Err(BadgeError::MessageNumberOutOfRange(N_MESSAGES))
+    ));
+    assert!(matches!(
+        badge.write_graphics_preview(0, GraphicsProtocol::Sixel, &options, &mut output),
+        Err(BadgeError::NoDataToWrite)
+    ));
+
+    badge.messages[0].data = vec![0xAA; BADGE_MSG_FONT_HEIGHT];
+    let mut output = Vec::<u8>::new();
+    assert!(
+        badge
+            .write_graphics_preview(0, GraphicsProtocol::Sixel, &options, &mut output)
+            .is_ok()
+    );
+    assert!(output.starts_with(b"\x1bPq\"1;1;64;88"));
+
+    let mut output = Vec::<u8>::new();
+    assert!(
+        badge
+            .write_graphics_preview(0, GraphicsProtocol::Kitty, &options, &mut output)
+            .is_ok()
+    );
+    assert!(output.starts_with(b"\x1b_Ga=T,f=100"));
+}
+
+#[test]
+fn test_write_simulation() {
+    let mut badge = Badge::new().unwrap();
+
+    let mut data = Vec::<u8>::new();
+    assert!(matches!(
+        badge.write_simulation(44, 50, AnimationFormat::Gif, &mut data),
+        Err(BadgeError::NoDataToWrite)
+    ));
+
+    badge.messages[0].data = vec![0xFF; BADGE_MSG_FONT_HEIGHT];
+    let mut data = Vec::<u8>::new();
+    assert!(
+        badge
+            .write_simulation(44, 50, AnimationFormat::Gif, &mut data)
+            .is_ok()
+    );
+    assert_eq!(&data[0..6], b"GIF89a");
+
+    let mut data = Vec::<u8>::new();
+    assert!(
+        badge
+            .write_simulation(44, 50, AnimationFormat::Png, &mut data)
+            .is_ok()
+    );
+    assert_eq!(
+        &data[0..8],
+        &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]
+    );
+}
@@ -0,0 +1,547 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+use crate::badge::canvas::Canvas;
+use crate::badge::led_render::Color;
+
+/// Method to convert the grayscale image into the monochrome bitmap
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum DitherMethod {
+    /// Fixed threshold
+    Threshold,
+    /// Threshold chosen by Otsu's method
+    Otsu,
+    /// Floyd-Steinberg error diffusion
+    FloydSteinberg,
+    /// Atkinson error diffusion
+    Atkinson,
+    /// Ordered dithering with the 4x4 Bayer matrix
+    Bayer,
+}
+
+impl DitherMethod {
+    pub fn values() -> impl Iterator<Item = DitherMethod> {
+        [
+            DitherMethod::Threshold,
+            DitherMethod::Otsu,
+            DitherMethod::FloydSteinberg,
+            DitherMethod::Atkinson,
+            DitherMethod::Bayer,
+        ]
+        .into_iter()
+    }
+}
+
+impl fmt::Display for DitherMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(format!("{:?}", self).to_lowercase().as_str())
+    }
+}
+
+impl FromStr for DitherMethod {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        DitherMethod::values()
+            .find(|&v| v.to_string().as_str() == value)
+            .ok_or(())
+    }
+}
+
+/// Channel or luminance formula to convert the color image into the grayscale
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum GrayChannel {
+    /// ITU-R BT.709 luma
+    Rec709,
+    /// ITU-R BT.601 luma
+    Rec601,
+    /// Average of red, green and blue
+    Average,
+    /// Red channel only
+    Red,
+    /// Green channel only
+    Green,
+    /// Blue channel only
+    Blue,
+}
+
+impl GrayChannel {
+    pub fn values() -> impl Iterator<Item = GrayChannel> {
+        [
+            GrayChannel::Rec709,
+            GrayChannel::Rec601,
+            GrayChannel::Average,
+            GrayChannel::Red,
+            GrayChannel::Green,
+            GrayChannel::Blue,
+        ]
+        .into_iter()
+    }
+
+    /// Gray level of the color
+    fn gray(&self, r: u8, g: u8, b: u8) -> u8 {
+        let (r, g, b) = (r as u32, g as u32, b as u32);
+        match self {
+            // luma coefficients in 8-bit fixed point
+            GrayChannel::Rec709 => ((54 * r + 183 * g + 19 * b) >> 8) as u8,
+            GrayChannel::Rec601 => ((77 * r + 150 * g + 29 * b) >> 8) as u8,
+            GrayChannel::Average => ((r + g + b) / 3) as u8,
+            GrayChannel::Red => r as u8,
+            GrayChannel::Green => g as u8,
+            GrayChannel::Blue => b as u8,
+        }
+    }
+}
+
+impl fmt::Display for GrayChannel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(format!("{:?}", self).to_lowercase().as_str())
+    }
+}
+
+impl FromStr for GrayChannel {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        GrayChannel::values()
+            .find(|&v| v.to_string().as_str() == value)
+            .ok_or(())
+    }
+}
+
+#[test]
+fn test_gray_channel() {
+    assert_eq!(GrayChannel::from_str("rec601"), Ok(GrayChannel::Rec601));
+    assert_eq!(GrayChannel::Blue.to_string(), "blue");
+    assert_eq!(GrayChannel::Rec601.gray(0xFF, 0xFF, 0xFF), 0xFF);
+    assert_eq!(GrayChannel::Average.gray(0x30, 0x60, 0x90), 0x60);
+    assert_eq!(GrayChannel::Green.gray(0x30, 0x60, 0x90), 0x60);
+}
+
+/// RGBA image with 8 bits per channel
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RgbaImage {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) pixels: Vec<[u8; 4]>,
+}
+
+impl RgbaImage {
+    /// Create a transparent image
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0; 4]; width * height],
+        }
+    }
+
+    /// Copy the rectangle of the image; the rectangle must be inside the image
+    pub(crate) fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> RgbaImage {
+        let pixels = (y..y + height)
+            .flat_map(|y| &self.pixels[y * self.width + x..y * self.width + x + width])
+            .copied()
+            .collect();
+        RgbaImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Composite the image over the opaque `background` color
+    pub(crate) fn composite(&self, background: Color) -> RgbaImage {
+        let pixels = self
+            .pixels
+            .iter()
+            .map(|&[r, g, b, a]| {
+                let blend = |v: u8, bg: u8| {
+                    ((v as u32 * a as u32 + bg as u32 * (0xFF - a as u32) + 0x7F) / 0xFF) as u8
+                };
+                [
+                    blend(r, background.r),
+                    blend(g, background.g),
+                    blend(b, background.b),
+                    0xFF,
+                ]
+            })
+            .collect();
+        RgbaImage {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+
+    /// Convert into the grayscale image of the channel composited over black.
+    pub(crate) fn to_gray_with(&self, channel: GrayChannel) -> GrayImage {
+        let pixels = self
+            .pixels
+            .iter()
+            .map(|&[r, g, b, a]| (channel.gray(r, g, b) as u32 * a as u32 / 0xFF) as u8)
+            .collect();
+        GrayImage {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+
+    /// Take the alpha channel as the grayscale image
+    pub(crate) fn alpha(&self) -> GrayImage {
+        GrayImage {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|&[_, _, _, a]| a).collect(),
+        }
+    }
+}
+
+#[test]
+fn test_rgba_image_crop() {
+    let mut image = RgbaImage::new(3, 3);
+    for (i, pixel) in image.pixels.iter_mut().enumerate() {
+        *pixel = [i as u8, 0, 0, 0xFF];
+    }
+    let cropped = image.crop(1, 1, 2, 2);
+    assert_eq!((cropped.width, cropped.height), (2, 2));
+    assert_eq!(
+        cropped.pixels.iter().map(|p| p[0]).collect::<Vec<_>>(),
+        vec![4, 5, 7, 8]
+    );
+}
+
+#[test]
+fn test_rgba_image_composite() {
+    let image = RgbaImage {
+        width: 3,
+        height: 1,
+        pixels: vec![
+            [0x10, 0x20, 0x30, 0xFF],
+            [0x10, 0x20, 0x30, 0x00],
+            [0x00, 0x00, 0x00, 0x80],
+        ],
+    };
+    assert_eq!(
+        image.composite(Color::new(0xFF, 0xFF, 0xFF)).pixels,
+        vec![
+            [0x10, 0x20, 0x30, 0xFF],
+            [0xFF, 0xFF, 0xFF, 0xFF],
+            [0x7F, 0x7F, 0x7F, 0xFF]
+        ]
+    );
+}
+
+#[test]
+fn test_rgba_image_to_gray() {
+    let image = RgbaImage {
+        width: 4,
+        height: 1,
+        pixels: vec![
+            [0xFF, 0xFF, 0xFF, 0xFF],
+            [0xFF, 0x00, 0x00, 0xFF],
+            [0x00, 0xFF, 0x00, 0xFF],
+            [0xFF, 0xFF, 0xFF, 0x00],
+        ],
+    };
+    assert_eq!(
+        image.to_gray_with(GrayChannel::Rec709).pixels,
+        vec![0xFF, 53, 182, 0]
+    );
+    assert_eq!(
+        image.to_gray_with(GrayChannel::Red).pixels,
+        vec![0xFF, 0xFF, 0, 0]
+    );
+    assert_eq!(image.alpha().pixels, vec![0xFF, 0xFF, 0xFF, 0]);
+}
+
+/// 8-bit grayscale image
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GrayImage {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) pixels: Vec<u8>,
+}
+
+/// Source pixel indices and their weights contributing to each destination pixel on an axis
+fn area_weights(src_len: usize, dst_len: usize) -> Vec<Vec<(usize, f32)>> {
+    let ratio = src_len as f32 / dst_len as f32;
+    (0..dst_len)
+        .map(|d| {
+            let start = d as f32 * ratio;
+            let end = (d + 1) as f32 * ratio;
+            (start.floor() as usize..(end.ceil() as usize).min(src_len))
+                .map(|s| {
+                    let overlap = end.min((s + 1) as f32) - start.max(s as f32);
+                    (s, overlap / ratio)
+                })
+                .filter(|&(_, w)| w > 0.0)
+                .collect()
+        })
+        .collect()
+}
+
+impl GrayImage {
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    /// Negate the gray levels
+    pub(crate) fn invert(&self) -> GrayImage {
+        GrayImage {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|&v| 0xFF - v).collect(),
+        }
+    }
+
+    /// Resize by averaging the covered area of the source pixels
+    pub(crate) fn resize(&self, width: usize, height: usize) -> GrayImage {
+        if (width, height) == (self.width, self.height) {
+            return self.clone();
+        }
+        let x_weights = area_weights(self.width, width);
+        let y_weights = area_weights(self.height, height);
+
+        let mut horizontal = vec![0f32; width * self.height];
+        for y in 0..self.height {
+            for (x, weights) in x_weights.iter().enumerate() {
+                horizontal[y * width + x] = weights
+                    .iter()
+                    .map(|&(s, w)| self.pixels[y * self.width + s] as f32 * w)
+                    .sum();
+            }
+        }
+
+        let mut resized = GrayImage::new(width, height);
+        for (y, weights) in y_weights.iter().enumerate() {
+            for x in 0..width {
+                let v: f32 = weights
+                    .iter()
+                    .map(|&(s, w)| horizontal[s * width + x] * w)
+                    .sum();
+                resized.pixels[y * width + x] = v.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        resized
+    }
+
+    /// Scale to `height` keeping the aspect ratio.
+    ///
+    /// If the scaled image gets wider than `max_width`, it is shrunk to `max_width`
+    /// and centered vertically instead.
+    pub(crate) fn fit(&self, height: usize, max_width: Option<usize>) -> GrayImage {
+        if self.width == 0 || self.height == 0 {
+            return GrayImage::new(0, height);
+        }
+        let scaled_width = ((self.width * height) as f32 / self.height as f32).round() as usize;
+        let (width, scaled_height) = match max_width {
+            Some(max_width) if scaled_width > max_width => {
+                let scaled_height =
+                    ((self.height * max_width) as f32 / self.width as f32).round() as usize;
+                (max_width, scaled_height.clamp(1, height))
+            }
+            _ => (scaled_width.max(1), height),
+        };
+
+        let scaled = self.resize(width, scaled_height);
+        let mut fitted = GrayImage::new(width, height);
+        let top = (height - scaled_height) / 2;
+        fitted.pixels[top * width..(top + scaled_height) * width].copy_from_slice(&scaled.pixels);
+        fitted
+    }
+
+    /// Light up the pixels brighter than or equal to `level`
+    pub(crate) fn threshold(&self, level: u8) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for (i, &v) in self.pixels.iter().enumerate() {
+            canvas.pixels[i] = (v >= level) as u8;
+        }
+        canvas
+    }
+
+    /// Threshold level maximizing the between-class variance (Otsu's method)
+    pub(crate) fn otsu_level(&self) -> u8 {
+        let mut histogram = [0usize; 256];
+        for &v in self.pixels.iter() {
+            histogram[v as usize] += 1;
+        }
+        let total = self.pixels.len() as f64;
+        let sum = (0..256).map(|i| (i * histogram[i]) as f64).sum::<f64>();
+
+        let (mut best_level, mut best_variance) = (0x80, 0.0);
+        let (mut count_below, mut sum_below) = (0.0, 0.0);
+        for level in 1..256 {
+            count_below += histogram[level - 1] as f64;
+            sum_below += ((level - 1) * histogram[level - 1]) as f64;
+            let count_above = total - count_below;
+            if count_below == 0.0 || count_above == 0.0 {
+                continue;
+            }
+            let mean_below = sum_below / count_below;
+            let mean_above = (sum - sum_below) / count_above;
+            let variance = count_below * count_above * (mean_below - mean_above).powi(2);
+            if variance > best_variance {
+                (best_level, best_variance) = (level as u8, variance);
+            }
+        }
+        best_level
+    }
+
+    /// Diffuse the quantization error to the neighbors by the weights of (dx, dy, weight)
+    fn diffuse_error(&self, weights: &[(isize, isize, f32)]) -> Canvas {
+        let mut values = self.pixels.iter().map(|&v| v as f32).collect::<Vec<_>>();
+        let mut canvas = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let v = values[y * self.width + x];
+                let lit = v >= 128.0;
+                canvas.set(x, y, lit);
+                let error = v - if lit { 255.0 } else { 0.0 };
+                for &(dx, dy, weight) in weights {
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    if (0..self.width as isize).contains(&nx) && ny < self.height as isize {
+                        values[ny as usize * self.width + nx as usize] += error * weight;
+                    }
+                }
+            }
+        }
+        canvas
+    }
+
+    /// Convert into the monochrome bitmap by `method`; `level` is for the fixed threshold.
+    pub(crate) fn dither(&self, method: DitherMethod, level: u8) -> Canvas {
+        const BAYER_MATRIX: [[u8; 4]; 4] =
+            [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+        match method {
+            DitherMethod::Threshold => self.threshold(level),
+            DitherMethod::Otsu => self.threshold(self.otsu_level()),
+            DitherMethod::FloydSteinberg => self.diffuse_error(&[
+                (1, 0, 7.0 / 16.0),
+                (-1, 1, 3.0 / 16.0),
+                (0, 1, 5.0 / 16.0),
+                (1, 1, 1.0 / 16.0),
+            ]),
+            DitherMethod::Atkinson => self.diffuse_error(&[
+                (1, 0, 1.0 / 8.0),
+                (2, 0, 1.0 / 8.0),
+                (-1, 1, 1.0 / 8.0),
+                (0, 1, 1.0 / 8.0),
+                (1, 1, 1.0 / 8.0),
+                (0, 2, 1.0 / 8.0),
+            ]),
+            DitherMethod::Bayer => {
+                let mut canvas = Canvas::new(self.width, self.height);
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        let level = BAYER_MATRIX[y % 4][x % 4] as u32 * 16 + 8;
+                        canvas.set(x, y, self.pixels[y * self.width + x] as u32 >= level);
+                    }
+                }
+                canvas
+            }
+        }
+    }
+}
+
+#[test]
+fn test_gray_image_resize() {
+    let image = GrayImage {
+        width: 4,
+        height: 2,
+        pixels: vec![0, 0xFF, 0xFF, 0xFF, 0, 0, 0xFF, 0xFF],
+    };
+    assert_eq!(image.resize(2, 1).pixels, vec![64, 0xFF]);
+    assert_eq!(
+        image.resize(8, 2).pixels,
+        vec![
+            0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF
+        ]
+    );
+    assert_eq!(image.resize(4, 2), image);
+}
+
+#[test]
+fn test_gray_image_fit() {
+    let image = GrayImage {
+        width: 4,
+        height: 2,
+        pixels: vec![0xFF; 8],
+    };
+    let fitted = image.fit(4, None);
+    assert_eq!((fitted.width, fitted.height), (8, 4));
+    assert!(fitted.pixels.iter().all(|&v| v == 0xFF));
+
+    let fitted = image.fit(4, Some(4));
+    assert_eq!((fitted.width, fitted.height), (4, 4));
+    #[rustfmt::skip]
+    assert_eq!(fitted.pixels, vec![
+        0x00, 0x00, 0x00, 0x00,
+        0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0xFF, 0xFF, 0xFF,
+        0x00, 0x00, 0x00, 0x00,
+    ]);
+}
+
+#[test]
+fn test_gray_image_threshold() {
+    let image = GrayImage {
+        width: 3,
+        height: 1,
+        pixels: vec![0x7F, 0x80, 0xFF],
+    };
+    assert_eq!(image.threshold(0x80).pixels, vec![0, 1, 1]);
+}
+
+#[test]
+fn test_gray_image_otsu_level() {
+    let image = GrayImage {
+        width: 6,
+        height: 1,
+        pixels: vec![0x10, 0x20, 0x18, 0xC0, 0xD0, 0xC8],
+    };
+    let level = image.otsu_level();
+    assert!((0x21..=0xC0).contains(&level), "{}", level);
+    assert_eq!(
+        image.dither(DitherMethod::Otsu, 0).pixels,
+        vec![0, 0, 0, 1, 1, 1]
+    );
+}
+
+#[test]
+fn test_gray_image_dither() {
+    let gray = |v: u8| GrayImage {
+        width: 8,
+        height: 8,
+        pixels: vec![v; 64],
+    };
+    let lit_count = |canvas: Canvas| canvas.pixels.iter().filter(|&&v| v != 0).count();
+
+    for method in DitherMethod::values() {
+        assert_eq!(lit_count(gray(0x00).dither(method, 0x80)), 0, "{}", method);
+        assert_eq!(lit_count(gray(0xFF).dither(method, 0x80)), 64, "{}", method);
+    }
+    // the middle gray is lit entirely by the threshold at the same level,
+    // and about half of the pixels are lit by the dithering methods
+    assert_eq!(
+        lit_count(gray(0x80).dither(DitherMethod::Threshold, 0x80)),
+        64
+    );
+    assert_eq!(lit_count(gray(0x80).dither(DitherMethod::Bayer, 0x80)), 32);
+    assert_eq!(
+        lit_count(gray(0x80).dither(DitherMethod::FloydSteinberg, 0x80)),
+        32
+    );
+    let atkinson = lit_count(gray(0x80).dither(DitherMethod::Atkinson, 0x80));
+    assert!((24..=40).contains(&atkinson), "{}", atkinson);
+    assert_eq!(
+        DitherMethod::from_str("floydsteinberg").unwrap(),
+        DitherMethod::FloydSteinberg
+    );
+}
@@ -0,0 +1,315 @@
+use crate::badge::canvas::{Canvas, vec2canvas};
+use crate::badge::{BADGE_MSG_FONT_HEIGHT, Badge, BadgeEffect, BadgeMessage};
+
+/// Number of steps a still page stays on the display
+const HOLD_STEPS: usize = 10;
+
+/// Number of steps each frame of the animation stays on the display
+const ANIMATION_FRAME_STEPS: usize = 4;
+
+/// Interval of the blink (flash) in milliseconds
+const BLINK_INTERVAL_MS: u32 = 500;
+
+/// Interval of an effect step at the given speed [1..8] in milliseconds
+///
+/// The firmware timing is not documented; this is an approximation of the real badges.
+fn step_interval(speed: u8) -> u32 {
+    (9 - speed.clamp(1, 8) as u32) * 25
+}
+
+#[test]
+fn test_step_interval() {
+    assert_eq!(step_interval(1), 200);
+    assert_eq!(step_interval(8), 25);
+}
+
+/// Draw a display image whose pixel (x, y) is lit iff `lit(x, y)` returns true.
+fn draw(width: usize, height: usize, lit: impl Fn(isize, isize) -> bool) -> Canvas {
+    let mut canvas = Canvas::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            canvas.set(x, y, lit(x as isize, y as isize));
+        }
+    }
+    canvas
+}
+
+/// Display images of each step of the effect
+fn effect_steps(effect: BadgeEffect, message: &Canvas, width: usize, height: usize) -> Vec<Canvas> {
+    let (w, h) = (width as isize, height as isize);
+    let lit = |x: isize, y: isize| x >= 0 && y >= 0 && message.get(x as usize, y as usize);
+    let page_count = message.width.div_ceil(width).max(1) as isize;
+    let hold = |page: isize| {
+        let still = draw(width, height, |x, y| lit(page * w + x, y));
+        vec![still; HOLD_STEPS]
+    };
+
+    match effect {
+        BadgeEffect::Left => {
+            let message_width = message.width as isize;
+            (0..=message_width + w)
+                .map(|t| draw(width, height, |x, y| lit(x + t - w, y)))
+                .collect()
+        }
+        BadgeEffect::Right => {
+            let message_width = message.width as isize;
+            (0..=message_width + w)
+                .map(|t| draw(width, height, |x, y| lit(x - t + message_width, y)))
+                .collect()
+        }
+        BadgeEffect::Up | BadgeEffect::Down => {
+            let sign = if effect == BadgeEffect::Up { 1 } else { -1 };
+            (0..page_count)
+                .flat_map(|page| {
+                    let shifted = |offset: isize| {
+                        draw(width, height, move |x, y| {
+                            lit(page * w + x, y + sign * offset)
+                        })
+                    };
+                    let enter = (1..=h).rev().map(|o| shifted(-o));
+                    let exit = (1..=h).map(shifted);
+                    enter.chain(hold(page)).chain(exit).collect::<Vec<_>>()
+                })
+                .collect()
+        }
+        BadgeEffect::Freeze => (0..page_count).flat_map(hold).collect(),
+        BadgeEffect::Animation => {
+            let stride = width.div_ceil(8) * 8;
+            (0..message.width.div_ceil(stride).max(1))
+                .flat_map(|i| {
+                    let frame = draw(width, height, |x, y| lit((i * stride) as isize + x, y));
+                    vec![frame; ANIMATION_FRAME_STEPS]
+                })
+                .collect()
+        }
+        BadgeEffect::Snow => (0..page_count)
+            .flat_map(|page| {
+                // pixels fall from the top; the lower rows start falling earlier
+                let falling = (0..2 * h - 1).map(move |t| {
+                    let mut canvas = Canvas::new(width, height);
+                    for y in 0..h {
+                        let row = (t - 2 * (h - 1 - y)).min(y);
+                        for x in 0..w {
+                            if row >= 0 && lit(page * w + x, y) {
+                                canvas.set(x as usize, row as usize, true);
+                            }
+                        }
+                    }
+                    canvas
+                });
+                falling.chain(hold(page)).collect::<Vec<_>>()
+            })
+            .collect(),
+        BadgeEffect::Volume => (0..page_count)
+            .flat_map(|page| {
+                // rows appear from the bottom like a level meter
+                let rising = (0..h).map(move |t| {
+                    draw(width, height, |x, y| y >= h - 1 - t && lit(page * w + x, y))
+                });
+                rising.chain(hold(page)).collect::<Vec<_>>()
+            })
+            .collect(),
+        BadgeEffect::Laser => (0..page_count)
+            .flat_map(|page| {
+                // columns are drawn one by one by the beam shot from the right edge
+                let shooting = (0..w).map(move |t| {
+                    draw(width, height, |x, y| {
+                        (x < t && lit(page * w + x, y)) || (x >= t && lit(page * w + t, y))
+                    })
+                });
+                shooting.chain(hold(page)).collect::<Vec<_>>()
+            })
+            .collect(),
+    }
+}
+
+/// Light up every other pixel along the edges of the display, shifted by `step`
+fn draw_border(canvas: &mut Canvas, step: usize) {
+    let (w, h) = (canvas.width, canvas.height);
+    if w < 2 || h < 2 {
+        return;
+    }
+    let perimeter = (0..w)
+        .map(|x| (x, 0))
+        .chain((1..h).map(|y| (w - 1, y)))
+        .chain((0..w - 1).rev().map(|x| (x, h - 1)))
+        .chain((1..h - 1).rev().map(|y| (0, y)));
+    for (i, (x, y)) in perimeter.enumerate() {
+        if (i + step).is_multiple_of(2) {
+            canvas.set(x, y, true);
+        }
+    }
+}
+
+/// Display of a message which is played from its start
+struct MessagePlayer<'a> {
+    message: &'a BadgeMessage,
+    steps: Vec<Canvas>,
+    interval: u32,
+}
+
+impl<'a> MessagePlayer<'a> {
+    fn new(message: &'a BadgeMessage, width: usize, height: usize) -> Self {
+        let canvas = vec2canvas(&message.data, BADGE_MSG_FONT_HEIGHT);
+        MessagePlayer {
+            message,
+            steps: effect_steps(message.effect, &canvas, width, height),
+            interval: step_interval(message.speed),
+        }
+    }
+
+    /// Time to play the message once in milliseconds
+    fn duration(&self) -> u32 {
+        self.steps.len() as u32 * self.interval
+    }
+
+    /// Display image at `local_time` milliseconds from the start of the message
+    fn frame_at(&self, local_time: u32) -> Canvas {
+        let step = (local_time / self.interval) as usize;
+        let mut frame = if self.message.blink && (local_time / BLINK_INTERVAL_MS) % 2 == 1 {
+            let first = &self.steps[0];
+            Canvas::new(first.width, first.height)
+        } else {
+            self.steps[step].clone()
+        };
+        if self.message.frame {
+            draw_border(&mut frame, step);
+        }
+        frame
+    }
+}
+
+/// Simulate the badge display and return the display image at every `time_step` milliseconds.
+///
+/// Non-empty messages are played once in order, as the badge does in a cycle of its playlist.
+pub(crate) fn simulate_badge(
+    badge: &Badge,
+    width: usize,
+    height: usize,
+    time_step: u32,
+) -> Vec<Canvas> {
+    let time_step = time_step.max(1);
+    let mut frames = Vec::new();
+    let mut time = 0u32;
+    let mut message_start = 0u32;
+
+    for message in badge.messages.iter().filter(|v| !v.data.is_empty()) {
+        let player = MessagePlayer::new(message, width, height);
+        let message_end = message_start + player.duration();
+
+        while time < message_end {
+            frames.push(player.frame_at(time - message_start));
+            time += time_step;
+        }
+        message_start = message_end;
+    }
+    frames
+}
+
+/// Simulate the message played once and return the display image at every `time_step` milliseconds.
+///
+/// An empty message gives no image.
+pub(crate) fn simulate_message(
+    message: &BadgeMessage,
+    width: usize,
+    height: usize,
+    time_step: u32,
+) -> Vec<Canvas> {
+    if message.data.is_empty() {
+        return Vec::new();
+    }
+    let player = MessagePlayer::new(message, width, height);
+    (0..player.duration())
+        .step_by(time_step.max(1) as usize)
+        .map(|time| player.frame_at(time))
+        .collect()
+}
+
+#[test]
+fn test_effect_steps() {
+    let mut message = Canvas::new(8, 2);
+    message.set(0, 0, true);
+
+    let steps = effect_steps(BadgeEffect::Left, &message, 4, 2);
+    assert_eq!(steps.len(), 8 + 4 + 1);
+    assert_eq!(steps[0].content_columns(), None);
+    assert_eq!(steps[1].content_columns(), Some(3..4));
+    assert_eq!(steps[4].content_columns(), Some(0..1));
+    assert_eq!(steps[5].content_columns(), None);
+
+    let steps = effect_steps(BadgeEffect::Right, &message, 4, 2);
+    assert_eq!(steps[8].content_columns(), Some(0..1));
+    assert_eq!(steps[11].content_columns(), Some(3..4));
+
+    let steps = effect_steps(BadgeEffect::Up, &message, 4, 2);
+    assert_eq!(steps.len(), 2 * (2 + HOLD_STEPS + 2));
+    assert!(steps[1].get(0, 1));
+    assert!(steps[2].get(0, 0));
+    assert!(steps[2 + HOLD_STEPS].pixels.iter().all(|&v| v == 0));
+
+    let steps = effect_steps(BadgeEffect::Down, &message, 4, 2);
+    assert!(!steps[1].get(0, 0) && !steps[1].get(0, 1));
+    assert!(steps[2].get(0, 0));
+    assert!(steps[2 + HOLD_STEPS].get(0, 1));
+
+    let steps = effect_steps(BadgeEffect::Freeze, &message, 4, 2);
+    assert_eq!(steps.len(), 2 * HOLD_STEPS);
+    assert!(steps[0].get(0, 0));
+
+    let steps = effect_steps(BadgeEffect::Animation, &message, 4, 2);
+    assert_eq!(steps.len(), ANIMATION_FRAME_STEPS);
+    assert!(steps[0].get(0, 0));
+
+    for effect in [BadgeEffect::Snow, BadgeEffect::Volume] {
+        let steps = effect_steps(effect, &message, 4, 2);
+        assert!(!steps[0].get(0, 0), "{}", effect);
+        assert!(steps[steps.len() / 2 - 1].get(0, 0), "{}", effect);
+    }
+
+    let steps = effect_steps(BadgeEffect::Laser, &message, 4, 2);
+    assert!(steps[0].get(3, 0));
+    assert!(!steps[1].get(3, 0));
+    assert!(steps[steps.len() / 2 - 1].get(0, 0));
+}
+
+#[test]
+fn test_simulate_badge() {
+    let mut badge = Badge::new().unwrap();
+    assert!(simulate_badge(&badge, 44, 11, 50).is_empty());
+
+    badge.messages[1].data = vec![0xFF; BADGE_MSG_FONT_HEIGHT];
+    badge.messages[1].effect = BadgeEffect::Freeze;
+    badge.messages[1].speed = 8;
+    let frames = simulate_badge(&badge, 44, 11, 25);
+    assert_eq!(frames.len(), HOLD_STEPS);
+    assert_eq!(frames[0].content_columns(), Some(0..8));
+
+    badge.messages[1].frame = true;
+    let frames = simulate_badge(&badge, 44, 11, 25);
+    assert!(frames[0].get(43, 0) ^ frames[1].get(43, 0));
+
+    badge.messages[1].speed = 1;
+    badge.messages[1].frame = false;
+    badge.messages[1].blink = true;
+    let frames = simulate_badge(&badge, 44, 11, BLINK_INTERVAL_MS);
+    assert_eq!(
+        frames.len(),
+        (HOLD_STEPS as u32 * 200).div_ceil(BLINK_INTERVAL_MS) as usize
+    );
+    assert!(frames[0].get(0, 0));
+    assert!(!frames[1].get(0, 0));
+}
+
+#[test]
+fn test_simulate_message() {
+    let mut badge = Badge::new().unwrap();
+    assert!(simulate_message(&badge.messages[0], 44, 11, 25).is_empty());
+
+    badge.messages[0].data = vec![0xFF; BADGE_MSG_FONT_HEIGHT];
+    badge.messages[0].effect = BadgeEffect::Left;
+    badge.messages[0].frame = true;
+    // the same images as the message is played on the badge
+    let frames = simulate_message(&badge.messages[0], 44, 11, 25);
+    assert_eq!(frames.len(), (8 + 44 + 1) * 200 / 25);
+    assert_eq!(frames, simulate_badge(&badge, 44, 11, 25));
+}
@@ -0,0 +1,160 @@
+use std::io::Write;
+
+use png::{BitDepth, ColorType, Encoder};
+
+use crate::badge::canvas::vec2canvas;
+use crate::badge::image_io::BadgeImageWriteError;
+use crate::badge::{BADGE_MSG_FONT_HEIGHT, Badge};
+
+/// Height of the separator line between the slots
+const SEPARATOR_HEIGHT: usize = 1;
+
+/// Gray level of the separator line; darker than the threshold to read the lit pixels
+const SEPARATOR_LEVEL: u8 = 0x40;
+
+/// Position of a message in the sprite sheet
+#[derive(Debug, PartialEq)]
+struct SpriteSlot {
+    message: usize,
+    y: usize,
+    width: usize,
+}
+
+/// Stack the non-empty messages vertically and return the slots and the sheet size
+fn layout(badge: &Badge) -> (Vec<SpriteSlot>, usize, usize) {
+    let mut slots = Vec::new();
+    let mut y = 0;
+    for (i, message) in badge.messages.iter().enumerate() {
+        if message.data.is_empty() {
+            continue;
+        }
+        if !slots.is_empty() {
+            y += SEPARATOR_HEIGHT;
+        }
+        slots.push(SpriteSlot {
+            message: i,
+            y,
+            width: 8 * message.data.len() / BADGE_MSG_FONT_HEIGHT,
+        });
+        y += BADGE_MSG_FONT_HEIGHT;
+    }
+    let width = slots.iter().map(|v| v.width).max().unwrap_or(0);
+    (slots, width, y)
+}
+
+/// Write all non-empty messages into a png image, separated by gray lines
+pub(crate) fn write_sprite_sheet_png<W: Write>(
+    badge: &Badge,
+    writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let (slots, width, height) = layout(badge);
+    let mut image_data = vec![0u8; width * height];
+    for (i, slot) in slots.iter().enumerate() {
+        if i > 0 {
+            let top = slot.y - SEPARATOR_HEIGHT;
+            image_data[top * width..slot.y * width].fill(SEPARATOR_LEVEL);
+        }
+        let canvas = vec2canvas(&badge.messages[slot.message].data, BADGE_MSG_FONT_HEIGHT);
+        for y in 0..canvas.height {
+            for x in (0..canvas.width).filter(|&x| canvas.get(x, y)) {
+                image_data[(slot.y + y) * width + x] = 0xFF;
+            }
+        }
+    }
+
+    let mut encoder = Encoder::new(writer, width as u32, height as u32);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image_data)?;
+    Ok(())
+}
+
+/// Write the json describing the position and the settings of each slot of the sprite sheet
+pub(crate) fn write_sprite_sheet_json<W: Write>(
+    badge: &Badge,
+    mut writer: W,
+) -> std::io::Result<()> {
+    let (slots, width, height) = layout(badge);
+    let slots = slots
+        .iter()
+        .map(|slot| {
+            let message = &badge.messages[slot.message];
+            format!(
+                r#"    {{"message": {}, "x": 0, "y": {}, "width": {}, "height": {}, "effect": "{}", "speed": {}, "blink": {}, "frame": {}}}"#,
+                slot.message,
+                slot.y,
+                slot.width,
+                BADGE_MSG_FONT_HEIGHT,
+                message.effect,
+                message.speed,
+                message.blink,
+                message.frame
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+    writeln!(
+        writer,
+        "{{\n  \"width\": {},\n  \"height\": {},\n  \"slots\": [\n{}\n  ]\n}}",
+        width, height, slots
+    )?;
+    writer.flush()
+}
+
+#[test]
+fn test_sprite_sheet() {
+    use crate::badge::BadgeEffect;
+    use std::io::Cursor;
+
+    let mut badge = Badge::new().unwrap();
+    badge.messages[1].data = vec![0x80; BADGE_MSG_FONT_HEIGHT];
+    badge.messages[4].data = vec![0x01; 2 * BADGE_MSG_FONT_HEIGHT];
+    badge.messages[4].effect = BadgeEffect::Snow;
+    badge.messages[4].frame = true;
+
+    let (slots, width, height) = layout(&badge);
+    assert_eq!(
+        slots,
+        vec![
+            SpriteSlot {
+                message: 1,
+                y: 0,
+                width: 8
+            },
+            SpriteSlot {
+                message: 4,
+                y: 12,
+                width: 16
+            },
+        ]
+    );
+    assert_eq!((width, height), (16, 23));
+
+    let mut png_data = Vec::new();
+    write_sprite_sheet_png(&badge, &mut png_data).unwrap();
+    let mut reader = png::Decoder::new(Cursor::new(&png_data))
+        .read_info()
+        .unwrap();
+    let mut image_data = vec![0; reader.output_buffer_size().unwrap()];
+    let info = reader.next_frame(&mut image_data).unwrap();
+    assert_eq!((info.width, info.height), (16, 23));
+    assert_eq!(&image_data[0..2], &[0xFF, 0x00]);
+    assert!(
+        image_data[11 * 16..12 * 16]
+            .iter()
+            .all(|&v| v == SEPARATOR_LEVEL)
+    );
+    assert_eq!(
+        (image_data[12 * 16 + 8], image_data[12 * 16 + 15]),
+        (0x00, 0xFF)
+    );
+
+    let mut json = Vec::new();
+    write_sprite_sheet_json(&badge, &mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert!(json.contains(r#""width": 16,"#));
+    assert!(json.contains(
+        r#"{"message": 4, "x": 0, "y": 12, "width": 16, "height": 11, "effect": "snow", "speed": 1, "blink": false, "frame": true}"#
+    ));
+}
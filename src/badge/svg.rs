@@ -0,0 +1,207 @@
+use resvg::tiny_skia::{FillRule, Paint, Pixmap, PixmapMut, Transform};
+use resvg::usvg::{self, Node, Opacity, PaintOrder, ShapeRendering, TextRendering, Tree};
+
+use crate::badge::image_io::{BadgeImageReadError, ImageImportOptions};
+use crate::badge::raster::RgbaImage;
+use crate::badge::{BADGE_MSG_FONT_HEIGHT, MAX_MESSAGE_WIDTH};
+
+/// Returns true if the data looks like svg or gzip-compressed svgz
+pub(crate) fn is_svg(signature: &[u8]) -> bool {
+    let text = signature.trim_ascii_start();
+    text.starts_with(b"<?xml") || text.starts_with(b"<svg") || signature.starts_with(&[0x1F, 0x8B])
+}
+
+/// Paint of the solid color; `None` for gradients and patterns
+fn solid_paint(paint: &usvg::Paint, opacity: Opacity, anti_alias: bool) -> Option<Paint<'static>> {
+    let usvg::Paint::Color(c) = paint else {
+        return None;
+    };
+    let mut solid = Paint::default();
+    solid.set_color_rgba8(c.red, c.green, c.blue, opacity.to_u8());
+    solid.anti_alias = anti_alias;
+    Some(solid)
+}
+
+/// Draw the path with the stroke width rounded to whole pixels.
+///
+/// Returns `None` if the path has the paint which is not a solid color.
+fn render_snapped_path(
+    path: &usvg::Path,
+    transform: Transform,
+    pixmap: &mut PixmapMut,
+) -> Option<()> {
+    let transform = transform.pre_concat(path.abs_transform());
+    let anti_alias = path.rendering_mode().use_shape_antialiasing();
+    let fill = match path.fill() {
+        Some(fill) => Some((
+            solid_paint(fill.paint(), fill.opacity(), anti_alias)?,
+            fill.rule(),
+        )),
+        None => None,
+    };
+    let stroke = match path.stroke() {
+        Some(stroke) => {
+            let paint = solid_paint(stroke.paint(), stroke.opacity(), anti_alias)?;
+            let mut stroke = stroke.to_tiny_skia();
+            let scale = (transform.sx * transform.sy - transform.kx * transform.ky)
+                .abs()
+                .sqrt();
+            if scale > 0.0 {
+                stroke.width = (stroke.width * scale).round().max(1.0) / scale;
+            }
+            Some((paint, stroke))
+        }
+        None => None,
+    };
+
+    let draw_fill = |pixmap: &mut PixmapMut| {
+        if let Some((paint, rule)) = &fill {
+            let rule = match rule {
+                usvg::FillRule::NonZero => FillRule::Winding,
+                usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+            };
+            pixmap.fill_path(path.data(), paint, rule, transform, None);
+        }
+    };
+    let draw_stroke = |pixmap: &mut PixmapMut| {
+        if let Some((paint, stroke)) = &stroke {
+            pixmap.stroke_path(path.data(), paint, stroke, transform, None);
+        }
+    };
+    if path.paint_order() == PaintOrder::FillAndStroke {
+        draw_fill(pixmap);
+        draw_stroke(pixmap);
+    } else {
+        draw_stroke(pixmap);
+        draw_fill(pixmap);
+    }
+    Some(())
+}
+
+/// Render the children of the group, snapping the strokes of the plain paths
+fn render_snapped_group(group: &usvg::Group, transform: Transform, pixmap: &mut PixmapMut) {
+    for node in group.children() {
+        let rendered = match node {
+            Node::Group(child)
+                if child.opacity() == Opacity::ONE
+                    && child.clip_path().is_none()
+                    && child.mask().is_none()
+                    && child.filters().is_empty() =>
+            {
+                render_snapped_group(child, transform, pixmap);
+                Some(())
+            }
+            Node::Path(path) if path.is_visible() => render_snapped_path(path, transform, pixmap),
+            _ => None,
+        };
+        if rendered.is_none() {
+            // render as is; resvg draws the node at the origin of its bounding box
+            if let Some(bbox) = node.abs_layer_bounding_box() {
+                let transform = transform
+                    .pre_concat(group.abs_transform())
+                    .pre_translate(bbox.x(), bbox.y());
+                resvg::render_node(node, transform, pixmap);
+            }
+        }
+    }
+}
+
+/// Rasterize the svg at the message height, fitting in `max_width` of the options
+pub(crate) fn render_svg(
+    data: &[u8],
+    options: &ImageImportOptions,
+) -> Result<RgbaImage, BadgeImageReadError> {
+    let mut usvg_options = usvg::Options::default();
+    usvg_options.fontdb_mut().load_system_fonts();
+    if !options.antialias {
+        usvg_options.shape_rendering = ShapeRendering::CrispEdges;
+        usvg_options.text_rendering = TextRendering::OptimizeSpeed;
+    }
+    let tree = Tree::from_data(data, &usvg_options)
+        .map_err(|e| BadgeImageReadError::InvalidImageData(format!("svg: {}", e)))?;
+
+    let size = tree.size();
+    let height = BADGE_MSG_FONT_HEIGHT as f32;
+    let mut scale = height / size.height();
+    if let Some(max_width) = options.max_width {
+        scale = scale.min(max_width as f32 / size.width());
+    }
+    let width = (size.width() * scale)
+        .round()
+        .clamp(1.0, MAX_MESSAGE_WIDTH as f32) as u32;
+    let mut top = (height - size.height() * scale) / 2.0;
+    if options.snap_strokes {
+        top = top.round();
+    }
+
+    let mut pixmap = Pixmap::new(width, BADGE_MSG_FONT_HEIGHT as u32).ok_or_else(|| {
+        BadgeImageReadError::InvalidImageData("svg: wrong image size".to_string())
+    })?;
+    let transform = Transform::from_row(scale, 0.0, 0.0, scale, 0.0, top);
+    if options.snap_strokes {
+        render_snapped_group(tree.root(), transform, &mut pixmap.as_mut());
+    } else {
+        resvg::render(&tree, transform, &mut pixmap.as_mut());
+    }
+
+    let mut image = RgbaImage::new(width as usize, BADGE_MSG_FONT_HEIGHT);
+    for (pixel, v) in image.pixels.iter_mut().zip(pixmap.pixels()) {
+        let v = v.demultiply();
+        *pixel = [v.red(), v.green(), v.blue(), v.alpha()];
+    }
+    Ok(image)
+}
+
+#[test]
+fn test_render_svg() {
+    // white 1-unit wide vertical line in 22x22 view box
+    let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="22" height="22">
+        <path d="M 11 0 V 22" stroke="#ffffff" stroke-width="1"/>
+    </svg>"##;
+    assert!(is_svg(svg));
+    assert!(!is_svg(b"P1 1 1 1"));
+
+    let alpha = |options: &ImageImportOptions| {
+        let image = render_svg(svg, options).unwrap();
+        assert_eq!((image.width, image.height), (11, BADGE_MSG_FONT_HEIGHT));
+        (0..image.width)
+            .map(|x| image.pixels[5 * image.width + x][3])
+            .collect::<Vec<_>>()
+    };
+
+    // half-pixel wide line is blurred without the snapping
+    let blurred = alpha(&ImageImportOptions::default());
+    assert!(blurred.iter().all(|&v| v < 0xC0), "{:?}", blurred);
+
+    let options = ImageImportOptions {
+        snap_strokes: true,
+        antialias: false,
+        ..Default::default()
+    };
+    let snapped = alpha(&options);
+    assert_eq!(
+        snapped.iter().filter(|&&v| v == 0xFF).count(),
+        1,
+        "{:?}",
+        snapped
+    );
+    assert!(
+        snapped.iter().all(|&v| v == 0 || v == 0xFF),
+        "{:?}",
+        snapped
+    );
+
+    let options = ImageImportOptions {
+        max_width: Some(4),
+        ..Default::default()
+    };
+    let image = render_svg(svg, &options).unwrap();
+    assert_eq!((image.width, image.height), (4, BADGE_MSG_FONT_HEIGHT));
+
+    assert!(render_svg(b"<svg", &ImageImportOptions::default()).is_err());
+
+    // extremely wide svg is cut at the width of the device memory
+    let wide = br#"<svg xmlns="http://www.w3.org/2000/svg" width="100000000" height="1"/>"#;
+    let image = render_svg(wide, &ImageImportOptions::default()).unwrap();
+    assert_eq!(image.width, MAX_MESSAGE_WIDTH);
+}
@@ -0,0 +1,446 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::io::Write;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use crate::badge::canvas::Canvas;
+use crate::badge::image_io::{BadgeImageWriteError, write_rgba_to_png};
+use crate::badge::led_render::{LedRenderOptions, render_leds};
+use crate::badge::raster::RgbaImage;
+
+/// Characters used to draw the bitmap in the terminal
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum PreviewStyle {
+    /// Unicode half blocks; a character cell shows 1x2 pixels
+    HalfBlock,
+    /// Unicode braille patterns; a character cell shows 2x4 pixels
+    Braille,
+}
+
+impl PreviewStyle {
+    pub fn values() -> impl Iterator<Item = PreviewStyle> {
+        [PreviewStyle::HalfBlock, PreviewStyle::Braille].into_iter()
+    }
+}
+
+impl fmt::Display for PreviewStyle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(format!("{:?}", self).to_lowercase().as_str())
+    }
+}
+
+impl FromStr for PreviewStyle {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        PreviewStyle::values()
+            .find(|&v| v.to_string().as_str() == value)
+            .ok_or(())
+    }
+}
+
+#[test]
+fn test_preview_style_from_str() {
+    assert_eq!(
+        PreviewStyle::from_str("halfblock").unwrap(),
+        PreviewStyle::HalfBlock
+    );
+    assert_eq!(
+        PreviewStyle::from_str("braille").unwrap(),
+        PreviewStyle::Braille
+    );
+    assert_eq!(PreviewStyle::from_str("ascii"), Err(()));
+}
+
+/// Draw the canvas with Unicode half block characters
+fn render_half_blocks(canvas: &Canvas) -> Vec<String> {
+    (0..canvas.height)
+        .step_by(2)
+        .map(|y| {
+            (0..canvas.width)
+                .map(|x| match (canvas.get(x, y), canvas.get(x, y + 1)) {
+                    (false, false) => ' ',
+                    (true, false) => '\u{2580}',
+                    (false, true) => '\u{2584}',
+                    (true, true) => '\u{2588}',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_render_half_blocks() {
+    let mut canvas = Canvas::new(4, 3);
+    canvas.set(1, 0, true);
+    canvas.set(2, 1, true);
+    canvas.set(3, 0, true);
+    canvas.set(3, 1, true);
+    canvas.set(0, 2, true);
+    assert_eq!(
+        render_half_blocks(&canvas),
+        vec![" \u{2580}\u{2584}\u{2588}", "\u{2580}   "]
+    );
+}
+
+/// Draw the canvas with Unicode braille pattern characters
+fn render_braille(canvas: &Canvas) -> Vec<String> {
+    // dot bits of the braille pattern indexed by [y][x] in a cell
+    const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+    (0..canvas.height)
+        .step_by(4)
+        .map(|y| {
+            (0..canvas.width)
+                .step_by(2)
+                .map(|x| {
+                    let mut bits = 0;
+                    for (q, row) in DOTS.iter().enumerate() {
+                        for (p, &bit) in row.iter().enumerate() {
+                            if canvas.get(x + p, y + q) {
+                                bits |= bit;
+                            }
+                        }
+                    }
+                    char::from_u32(0x2800 + bits).unwrap()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_render_braille() {
+    let mut canvas = Canvas::new(3, 5);
+    canvas.set(0, 0, true);
+    canvas.set(1, 3, true);
+    canvas.set(2, 4, true);
+    assert_eq!(
+        render_braille(&canvas),
+        vec!["\u{2881}\u{2800}", "\u{2800}\u{2801}"]
+    );
+}
+
+/// Draw the canvas as lines of text in the given style
+fn render_to_lines(canvas: &Canvas, style: PreviewStyle) -> Vec<String> {
+    match style {
+        PreviewStyle::HalfBlock => render_half_blocks(canvas),
+        PreviewStyle::Braille => render_braille(canvas),
+    }
+}
+
+/// Write the canvas as text in the given style
+pub(crate) fn write_preview<W: Write>(
+    canvas: &Canvas,
+    style: PreviewStyle,
+    mut writer: W,
+) -> std::io::Result<()> {
+    for line in render_to_lines(canvas, style) {
+        writeln!(writer, "{}", line)?;
+    }
+    writer.flush()
+}
+
+/// Play the frames in place using ANSI cursor movement, waiting `delay` between the frames
+pub(crate) fn play_preview<W: Write>(
+    frames: &[Canvas],
+    delay: Duration,
+    style: PreviewStyle,
+    mut writer: W,
+) -> std::io::Result<()> {
+    for (i, frame) in frames.iter().enumerate() {
+        let lines = render_to_lines(frame, style);
+        if i > 0 {
+            // move the cursor back to the top of the previous frame
+            write!(writer, "\x1b[{}A", lines.len())?;
+        }
+        for line in lines {
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_play_preview() {
+    let frames = vec![Canvas::new(2, 2); 2];
+    let mut output = Vec::new();
+    play_preview(
+        &frames,
+        Duration::ZERO,
+        PreviewStyle::HalfBlock,
+        &mut output,
+    )
+    .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "  \n\x1b[1A  \n");
+}
+
+/// Terminal graphics protocol to show images
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum GraphicsProtocol {
+    /// DEC sixel graphics
+    Sixel,
+    /// Kitty terminal graphics protocol
+    Kitty,
+}
+
+impl GraphicsProtocol {
+    pub fn values() -> impl Iterator<Item = GraphicsProtocol> {
+        [GraphicsProtocol::Sixel, GraphicsProtocol::Kitty].into_iter()
+    }
+
+    /// Guess the graphics protocol supported by the terminal from the environment variables
+    pub fn detect() -> Option<GraphicsProtocol> {
+        Self::detect_with(|name| std::env::var(name).ok())
+    }
+
+    fn detect_with(env: impl Fn(&str) -> Option<String>) -> Option<GraphicsProtocol> {
+        let term = env("TERM").unwrap_or_default();
+        let term_program = env("TERM_PROGRAM").unwrap_or_default();
+
+        if env("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || ["WezTerm", "ghostty"].contains(&term_program.as_str())
+        {
+            Some(GraphicsProtocol::Kitty)
+        } else if ["sixel", "mlterm", "foot", "yaft", "contour"]
+            .iter()
+            .any(|v| term.contains(v))
+            || ["iTerm.app", "mintty"].contains(&term_program.as_str())
+        {
+            Some(GraphicsProtocol::Sixel)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for GraphicsProtocol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(format!("{:?}", self).to_lowercase().as_str())
+    }
+}
+
+impl FromStr for GraphicsProtocol {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        GraphicsProtocol::values()
+            .find(|&v| v.to_string().as_str() == value)
+            .ok_or(())
+    }
+}
+
+#[test]
+fn test_graphics_protocol_detect() {
+    let env = |vars: &'static [(&'static str, &'static str)]| {
+        move |name: &str| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    };
+    assert_eq!(
+        GraphicsProtocol::detect_with(env(&[("TERM", "xterm-kitty")])),
+        Some(GraphicsProtocol::Kitty)
+    );
+    assert_eq!(
+        GraphicsProtocol::detect_with(env(&[("TERM", "xterm"), ("KITTY_WINDOW_ID", "1")])),
+        Some(GraphicsProtocol::Kitty)
+    );
+    assert_eq!(
+        GraphicsProtocol::detect_with(env(&[("TERM", "foot")])),
+        Some(GraphicsProtocol::Sixel)
+    );
+    assert_eq!(
+        GraphicsProtocol::detect_with(env(&[("TERM", "xterm-256color")])),
+        None
+    );
+    assert_eq!(
+        GraphicsProtocol::from_str("sixel").unwrap(),
+        GraphicsProtocol::Sixel
+    );
+}
+
+/// Write the image with the DEC sixel graphics sequence
+fn write_sixel<W: Write>(image: &RgbaImage, writer: &mut W) -> std::io::Result<()> {
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let indices = image
+        .pixels
+        .iter()
+        .map(|&[r, g, b, _]| {
+            // sixel has 256 color registers; share the nearest one when they run out
+            if let Some(i) = palette.iter().position(|&c| c == [r, g, b]) {
+                i
+            } else if palette.len() < 256 {
+                palette.push([r, g, b]);
+                palette.len() - 1
+            } else {
+                let distance = |c: &[u8; 3]| {
+                    (0..3)
+                        .map(|i| (c[i] as i32 - [r, g, b][i] as i32).pow(2))
+                        .sum::<i32>()
+                };
+                (0..palette.len())
+                    .min_by_key(|&i| distance(&palette[i]))
+                    .unwrap()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    write!(writer, "\x1bPq\"1;1;{};{}", image.width, image.height)?;
+    for (i, c) in palette.iter().enumerate() {
+        let percent = |v: u8| v as u32 * 100 / 0xFF;
+        write!(
+            writer,
+            "#{};2;{};{};{}",
+            i,
+            percent(c[0]),
+            percent(c[1]),
+            percent(c[2])
+        )?;
+    }
+
+    for band in (0..image.height).step_by(6) {
+        let rows = band..(band + 6).min(image.height);
+        let mut band_colors = rows
+            .clone()
+            .flat_map(|y| {
+                indices[y * image.width..(y + 1) * image.width]
+                    .iter()
+                    .copied()
+            })
+            .collect::<Vec<_>>();
+        band_colors.sort_unstable();
+        band_colors.dedup();
+
+        for color in band_colors {
+            let sixels = (0..image.width).map(|x| {
+                rows.clone()
+                    .filter(|&y| indices[y * image.width + x] == color)
+                    .fold(0u8, |bits, y| bits | (1 << (y - band)))
+            });
+            write!(writer, "#{}", color)?;
+            // run-length encoding of the same sixel characters
+            let mut runs: Vec<(u8, usize)> = Vec::new();
+            for sixel in sixels {
+                match runs.last_mut() {
+                    Some((last, count)) if *last == sixel => *count += 1,
+                    _ => runs.push((sixel, 1)),
+                }
+            }
+            for (sixel, count) in runs {
+                let c = (0x3F + sixel) as char;
+                if count > 3 {
+                    write!(writer, "!{}{}", count, c)?;
+                } else {
+                    write!(writer, "{}", c.to_string().repeat(count))?;
+                }
+            }
+            write!(writer, "$")?;
+        }
+        write!(writer, "-")?;
+    }
+    writeln!(writer, "\x1b\\")
+}
+
+#[test]
+fn test_write_sixel() {
+    let mut image = RgbaImage::new(5, 2);
+    image.pixels = vec![[0xFF, 0xFF, 0xFF, 0xFF]; 10];
+    image.pixels[9] = [0, 0, 0, 0xFF];
+    let mut output = Vec::new();
+    write_sixel(&image, &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "\x1bPq\"1;1;5;2#0;2;100;100;100#1;2;0;0;0#0!4B@$#1!4?A$-\x1b\\\n"
+    );
+}
+
+/// Encode the data in base64
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &v)| bits | (v as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(TABLE[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[test]
+fn test_base64() {
+    assert_eq!(base64(b""), "");
+    assert_eq!(base64(b"f"), "Zg==");
+    assert_eq!(base64(b"fo"), "Zm8=");
+    assert_eq!(base64(b"foo"), "Zm9v");
+    assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+}
+
+/// Write the png data with the kitty terminal graphics protocol
+fn write_kitty<W: Write>(png_data: &[u8], writer: &mut W) -> std::io::Result<()> {
+    // the payload must be sent in chunks of up to 4096 bytes
+    const CHUNK_SIZE: usize = 4096;
+    let encoded = base64(png_data);
+    let chunks = encoded.as_bytes().chunks(CHUNK_SIZE).collect::<Vec<_>>();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            write!(writer, "\x1b_Ga=T,f=100,m={};", more)?;
+        } else {
+            write!(writer, "\x1b_Gm={};", more)?;
+        }
+        writer.write_all(chunk)?;
+        write!(writer, "\x1b\\")?;
+    }
+    writeln!(writer)
+}
+
+#[test]
+fn test_write_kitty() {
+    let mut output = Vec::new();
+    write_kitty(b"foo", &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "\x1b_Ga=T,f=100,m=0;Zm9v\x1b\\\n"
+    );
+
+    let mut output = Vec::new();
+    write_kitty(&[0; 3073], &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("\x1b_Ga=T,f=100,m=1;AAAA"));
+    assert!(output.ends_with("\x1b\\\x1b_Gm=0;AA==\x1b\\\n"));
+}
+
+/// Write the canvas as the LED-look image with the terminal graphics protocol
+pub(crate) fn write_graphics_preview<W: Write>(
+    canvas: &Canvas,
+    protocol: GraphicsProtocol,
+    options: &LedRenderOptions,
+    mut writer: W,
+) -> Result<(), BadgeImageWriteError> {
+    let image = render_leds(canvas, options);
+    match protocol {
+        GraphicsProtocol::Sixel => write_sixel(&image, &mut writer)?,
+        GraphicsProtocol::Kitty => {
+            let mut png_data = Vec::new();
+            write_rgba_to_png(&image, &mut png_data)?;
+            write_kitty(&png_data, &mut writer)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
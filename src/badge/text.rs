@@ -1,151 +1,574 @@
-use std::path::Path;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-use freetype::face::LoadFlag;
+use freetype::face::{KerningMode, LoadFlag};
 use freetype::freetype_sys::FT_Pos;
-use freetype::{Error, Library};
-
-#[derive(Debug)]
-struct Canvas {
-    width: usize,
-    height: usize,
-    pixels: Vec<u8>,
-}
-
-impl Canvas {
-    fn new(width: usize, height: usize) -> Self {
-        let pixels = vec![0; width * height];
-        Self {
-            width,
-            height,
-            pixels,
-        }
-    }
-}
+use freetype::{Face, Library};
+use rustybuzz::ttf_parser::Tag;
+use rustybuzz::{Direction, Feature, UnicodeBuffer};
+use unicode_bidi::BidiInfo;
 
-/// Convert the canvas data into the led badge message data.
-fn canvas2vec(canvas: &Canvas) -> Vec<u8> {
-    let data_width = canvas.width.div_ceil(8);
-    let data_height = canvas.height;
+use crate::badge::bitmap_font::{BitmapFont, builtin_font, is_bitmap_font};
+use crate::badge::canvas::{Canvas, canvas2vec};
+use crate::badge::error::BadgeError;
+use crate::badge::font_selector::select_font_for_char;
 
-    let mut data = vec![0; data_width * data_height];
-    for (i, &v) in canvas.pixels.iter().enumerate() {
-        if v > 0 {
-            let canvas_x = i % canvas.width;
-            let canvas_y = i / canvas.width;
-
-            let data_x = canvas_x / 8;
-            let data_offset = canvas_x % 8;
-            let data_y = canvas_y;
-            let data_index = data_x * canvas.height + data_y;
+/// Options of the text layout
+#[derive(Debug, Clone)]
+pub struct TextRenderOptions {
+    /// Apply the kerning table of the font
+    pub kerning: bool,
+    /// Columns added between the letters; negative to narrow the text
+    pub letter_spacing: i32,
+    /// Columns added to each space between the words
+    pub word_spacing: i32,
+    /// Blank columns before the text
+    pub padding_left: usize,
+    /// Blank columns after the text
+    pub padding_right: usize,
+}
 
-            data[data_index] |= 0x80u8 >> data_offset as u8;
+impl Default for TextRenderOptions {
+    fn default() -> Self {
+        TextRenderOptions {
+            kerning: true,
+            letter_spacing: 0,
+            word_spacing: 0,
+            padding_left: 0,
+            padding_right: 0,
         }
     }
+}
 
-    data
+/// Font face to draw the glyphs with
+enum TextFace {
+    FreeType {
+        face: Face,
+        /// font file data to shape the text
+        data: Rc<Vec<u8>>,
+        index: usize,
+    },
+    Bitmap(Cow<'static, BitmapFont>),
 }
 
-#[test]
-fn test_canvas2vec() {
-    let vec = vec![0b10101010, 0b11001100, 0b11110000, 0b11111111];
-    let canvas = Canvas {
-        #[rustfmt::skip]
-        pixels: vec![
-            1, 0, 1, 0, 1, 0, 1, 0, 1, 1, 1, 1, 0, 0, 0, 0,
-            1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1,
-        ],
-        width: 16,
-        height: 2,
-    };
-    assert_eq!(canvas2vec(&canvas), vec);
-
-    let vec = vec![
-        0b10101010, 0b11001100, 0b11110000, 0b11111111, 0b10000000, 0b10000000,
-    ];
-    let canvas = Canvas {
-        #[rustfmt::skip]
-        pixels: vec![
-            1, 0, 1, 0, 1, 0, 1, 0, 1, 1, 1, 1, 0, 0, 0, 0, 1,
-            1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1,
-        ],
-        width: 17,
-        height: 2,
-    };
-    assert_eq!(canvas2vec(&canvas), vec);
+/// Glyph to draw: the character, or the glyph index given by shaping the text
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GlyphKey {
+    Char(char),
+    Index(u32),
 }
 
-/// Render text with given font configuration and return the led badge message data.
-pub(crate) fn render_text(
-    text: &str,
-    pixel_height: usize,
-    font_path: &Path,
-    font_index: usize,
-) -> Result<Vec<u8>, Error> {
-    fn ftpos2pixel(p: FT_Pos) -> usize {
-        p as usize / 64usize
+/// Glyph of the shaped text; the offsets are in 26.6 fixed point
+struct ShapedGlyph {
+    key: GlyphKey,
+    /// false for the marks placed over the preceding glyph
+    advances: bool,
+    /// true for the glyph of the space between the words
+    space: bool,
+    x_offset: FT_Pos,
+    y_offset: FT_Pos,
+}
+
+impl TextFace {
+    /// Open the bitmap font or the font of FreeType
+    fn open(
+        lib: &Library,
+        font_path: &Path,
+        font_index: usize,
+        pixel_height: usize,
+    ) -> Result<Self, BadgeError> {
+        if is_bitmap_font(font_path) {
+            let font = BitmapFont::load(font_path).map_err(|e| {
+                BadgeError::BitmapFontLoading(Some(font_path.display().to_string()), e)
+            })?;
+            Ok(TextFace::Bitmap(Cow::Owned(font)))
+        } else {
+            let data = Rc::new(
+                std::fs::read(font_path)
+                    .map_err(|e| BadgeError::FileIo(Some(font_path.display().to_string()), e))?,
+            );
+            let face = lib.new_memory_face(data.clone(), font_index as isize)?;
+            if face.is_scalable() {
+                face.set_pixel_sizes(0, pixel_height as u32)?;
+            }
+            Ok(TextFace::FreeType {
+                face,
+                data,
+                index: font_index,
+            })
+        }
     }
-    fn pixel2ftpos(p: usize) -> FT_Pos {
-        p as i64 * 64
+
+    fn has_glyph(&self, c: char) -> bool {
+        match self {
+            TextFace::FreeType { face, .. } => face.get_char_index(c as usize).is_some(),
+            TextFace::Bitmap(font) => font.has_glyph(c),
+        }
     }
 
-    let lib = Library::init()?;
-    let face = lib.new_face(font_path, font_index as isize)?;
+    /// Baseline in 26.6 fixed point to fit the face in the height
+    fn baseline(&self, pixel_height: usize) -> FT_Pos {
+        match self {
+            TextFace::FreeType { face, .. } => {
+                let descender = face.size_metrics().map_or(0, |metrics| metrics.descender);
+                pixel2ftpos(pixel_height) + descender
+            }
+            TextFace::Bitmap(font) => font.baseline(pixel_height) as FT_Pos * 64,
+        }
+    }
+
+    /// Shape the text of a single direction into the glyphs in the visual order
+    ///
+    /// Returns `None` if the face cannot be shaped, such as the bitmap fonts;
+    /// the characters are drawn one by one then.
+    fn shape(&self, text: &str, rtl: bool) -> Option<Vec<ShapedGlyph>> {
+        let TextFace::FreeType { face, data, index } = self else {
+            return None;
+        };
+        let metrics = face.size_metrics()?;
+        let shaper = rustybuzz::Face::from_slice(data, *index as u32)?;
 
-    if face.is_scalable() {
-        face.set_pixel_sizes(0, pixel_height as u32)?;
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.set_direction(if rtl {
+            Direction::RightToLeft
+        } else {
+            Direction::LeftToRight
+        });
+        // the glyphs keep the advances of FreeType; the layout applies the kerning
+        let features = [Feature::new(Tag::from_bytes(b"kern"), 0, ..)];
+        let glyphs = rustybuzz::shape(&shaper, &features, buffer);
+
+        // font units to 26.6 fixed point, i.e. FT_MulFix
+        let scale = |v: i32, scale: FT_Pos| (v as FT_Pos * scale + 0x8000) >> 16;
+        let shaped = glyphs
+            .glyph_infos()
+            .iter()
+            .zip(glyphs.glyph_positions())
+            .map(|(info, position)| ShapedGlyph {
+                key: GlyphKey::Index(info.glyph_id),
+                advances: position.x_advance != 0,
+                space: text[info.cluster as usize..].starts_with(char::is_whitespace),
+                x_offset: scale(position.x_offset, metrics.x_scale),
+                y_offset: scale(position.y_offset, metrics.y_scale),
+            })
+            .collect();
+        Some(shaped)
     }
 
-    let mut canvas = {
-        let mut width = 0;
-        for c in text.chars() {
-            face.load_char(c as usize, LoadFlag::RENDER | LoadFlag::TARGET_MONO)?;
-            width += ftpos2pixel(face.glyph().advance().x);
+    /// Kerning between the glyphs in pixels
+    fn kerning(&self, left: GlyphKey, right: GlyphKey) -> i32 {
+        let TextFace::FreeType { face, .. } = self else {
+            return 0; // the bitmap fonts have no kerning
+        };
+        if !face.has_kerning() {
+            return 0;
         }
-        Canvas::new(width, pixel_height)
-    };
+        let index = |key| match key {
+            GlyphKey::Char(c) => face.get_char_index(c as usize),
+            GlyphKey::Index(index) => Some(index),
+        };
+        // the default mode of FreeType weakens the kerning of the small sizes to nothing
+        match (index(left), index(right)) {
+            (Some(left), Some(right)) => face
+                .get_kerning(left, right, KerningMode::KerningUnfitted)
+                .map_or(0, |kerning| ftpos2pixel(kerning.x + 32)),
+            _ => 0,
+        }
+    }
+
+    /// Draw the glyph with the origin at (`pen_x`, `baseline`) and return the advance
+    ///
+    /// The glyph is only measured if `canvas` is `None`.
+    fn draw_glyph(
+        &self,
+        key: GlyphKey,
+        canvas: Option<&mut Canvas>,
+        pen_x: i32,
+        baseline: FT_Pos,
+    ) -> Result<i32, BadgeError> {
+        let face = match (self, key) {
+            (TextFace::FreeType { face, .. }, _) => face,
+            (TextFace::Bitmap(font), GlyphKey::Char(c)) => {
+                return Ok(font.draw_glyph(c, canvas, pen_x, ftpos2pixel(baseline)));
+            }
+            (TextFace::Bitmap(_), GlyphKey::Index(_)) => return Ok(0),
+        };
 
-    let mut pen_x = 0;
-    for c in text.chars() {
-        face.load_char(c as usize, LoadFlag::RENDER | LoadFlag::TARGET_MONO)?;
+        match key {
+            GlyphKey::Char(c) => {
+                face.load_char(c as usize, LoadFlag::RENDER | LoadFlag::TARGET_MONO)?
+            }
+            GlyphKey::Index(index) => {
+                face.load_glyph(index, LoadFlag::RENDER | LoadFlag::TARGET_MONO)?
+            }
+        }
         let glyph = face.glyph();
+        let advance = ftpos2pixel(glyph.advance().x).max(0);
+        let Some(canvas) = canvas else {
+            return Ok(advance);
+        };
+
         let bitmap = glyph.bitmap();
         let buffer = bitmap.buffer();
-
         let face_metrics = face.size_metrics().unwrap();
         let metrics = glyph.metrics();
         let pitch = bitmap.pitch() as usize;
         let rows = bitmap.rows() as usize;
-        let pen_start_x = pen_x
-            + if metrics.horiBearingX >= 0 {
-                ftpos2pixel(metrics.horiBearingX)
-            } else {
-                0
-            };
+        let pen_start_x = pen_x.saturating_add(ftpos2pixel(metrics.horiBearingX));
         let pen_start_y = if face_metrics.ascender == 0 {
             0 // some font does not have ascend.
         } else {
-            ftpos2pixel(
-                pixel2ftpos(pixel_height) - (-face_metrics.descender) - metrics.horiBearingY,
-            )
+            ftpos2pixel(baseline - metrics.horiBearingY)
         };
 
         for q in 0..rows {
             for p in 0..pitch {
                 for i in 0..8usize {
                     let pixel_val = buffer[q * pitch + p] & (0x80 >> i) as u8;
-                    let canvas_x = pen_start_x + p * 8 + i;
-                    let canvas_y = pen_start_y + q;
-                    if pixel_val != 0 && canvas_x < canvas.width && canvas_y < canvas.height {
-                        canvas.pixels[canvas_y * canvas.width + canvas_x] = 1;
+                    let canvas_x = pen_start_x.saturating_add((p * 8 + i) as i32);
+                    let canvas_y = pen_start_y + q as i32;
+                    if pixel_val != 0 && canvas_x >= 0 && canvas_y >= 0 {
+                        canvas.set(canvas_x as usize, canvas_y as usize, true);
+                    }
+                }
+            }
+        }
+        Ok(advance)
+    }
+}
+
+fn ftpos2pixel(p: FT_Pos) -> i32 {
+    p.div_euclid(64) as i32
+}
+
+fn pixel2ftpos(p: usize) -> FT_Pos {
+    p as FT_Pos * 64
+}
+
+/// Faces to draw the text: the selected face and the fallback faces for the missing characters
+struct TextFaces {
+    lib: Library,
+    faces: Vec<TextFace>,
+    /// index in `faces` of the fallback font files by the path and the face index;
+    /// `None` for the files failed to open
+    fallback_faces: HashMap<(PathBuf, usize), Option<usize>>,
+    builtin_added: bool,
+}
+
+impl TextFaces {
+    /// Index of the face to draw the character
+    ///
+    /// If no face has the glyph, fontconfig is asked for the font covering the character,
+    /// then the built-in font is tried. The first face draws the missing glyph at last.
+    fn face_for_char(&mut self, c: char, font_names: &[&str], pixel_height: usize) -> usize {
+        if let Some(index) = self.faces.iter().position(|face| face.has_glyph(c)) {
+            return index;
+        }
+
+        if let Ok((font_path, font_index)) = select_font_for_char(c, font_names, Some(pixel_height))
+            && let Some(index) = self.fallback_face(font_path, font_index, pixel_height)
+            && self.faces[index].has_glyph(c)
+        {
+            return index;
+        }
+        if !self.builtin_added && builtin_font().has_glyph(c) {
+            self.builtin_added = true;
+            self.faces
+                .push(TextFace::Bitmap(Cow::Borrowed(builtin_font())));
+            return self.faces.len() - 1;
+        }
+        0
+    }
+
+    /// Index of the fallback face of the font file, opened at the first time
+    ///
+    /// The face is kept even if it lacks the requested character,
+    /// since fontconfig may select the same file for the other characters.
+    fn fallback_face(
+        &mut self,
+        font_path: PathBuf,
+        font_index: usize,
+        pixel_height: usize,
+    ) -> Option<usize> {
+        let key = (font_path, font_index);
+        if let Some(&index) = self.fallback_faces.get(&key) {
+            return index;
+        }
+        let index = TextFace::open(&self.lib, &key.0, key.1, pixel_height)
+            .ok()
+            .map(|face| {
+                self.faces.push(face);
+                self.faces.len() - 1
+            });
+        self.fallback_faces.insert(key, index);
+        index
+    }
+}
+
+#[test]
+fn test_text_faces_fallback_face() {
+    // the font of the test has only 'A' and ','
+    let font_path = std::env::temp_dir().join("led-name-badge-test-text-faces-fallback.bdf");
+    std::fs::write(&font_path, crate::badge::bitmap_font::TEST_BDF).unwrap();
+    let mut faces = TextFaces {
+        lib: Library::init().unwrap(),
+        faces: vec![TextFace::Bitmap(Cow::Borrowed(builtin_font()))],
+        fallback_faces: HashMap::new(),
+        builtin_added: true,
+    };
+    let index = faces.fallback_face(font_path.clone(), 0, 11);
+    std::fs::remove_file(&font_path).unwrap();
+
+    assert_eq!(index, Some(1));
+    // the face is reused from the cache for another character
+    assert_eq!(faces.fallback_face(font_path.clone(), 0, 11), Some(1));
+    assert_eq!(faces.faces.len(), 2);
+    assert!(!faces.faces[1].has_glyph('B') && faces.faces[1].has_glyph(','));
+
+    assert_eq!(
+        faces.fallback_face(font_path.with_extension("none.bdf"), 0, 11),
+        None
+    );
+    assert_eq!(faces.faces.len(), 2);
+}
+
+/// Returns true for the invisible format characters such as the joiners and the bidi controls
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2069}' | '\u{FEFF}')
+}
+
+/// Returns true for the characters which only separate or join the others;
+/// they are drawn with the face of the preceding character not to break the shaping.
+fn is_separator(c: char) -> bool {
+    c.is_control() || c.is_whitespace() || is_invisible(c)
+}
+
+/// Glyph placed by the layout
+struct PlacedGlyph {
+    face: usize,
+    key: GlyphKey,
+    x: i32,
+    /// offset above the baseline in 26.6 fixed point
+    y_offset: FT_Pos,
+}
+
+/// Place the glyphs of the text in the visual order and return them with the width
+///
+/// The text is reordered with the Unicode bidirectional algorithm and
+/// each run of the same direction and face is shaped. The glyphs are then spaced by the options.
+fn layout_text(
+    faces: &mut TextFaces,
+    text: &str,
+    font_names: &[&str],
+    pixel_height: usize,
+    options: &TextRenderOptions,
+) -> Result<(Vec<PlacedGlyph>, i32), BadgeError> {
+    // face of each character indexed by the byte offset
+    let mut char_faces = vec![0; text.len()];
+    let mut previous = 0;
+    for (offset, c) in text.char_indices() {
+        let face = if is_separator(c) {
+            Some(previous)
+                .filter(|&i| faces.faces[i].has_glyph(c))
+                .unwrap_or(0)
+        } else {
+            faces.face_for_char(c, font_names, pixel_height)
+        };
+        char_faces[offset] = face;
+        previous = face;
+    }
+
+    let bidi = BidiInfo::new(text, None);
+    let mut glyphs = Vec::new();
+    let padding_left = i32::try_from(options.padding_left).unwrap_or(i32::MAX);
+    let padding_right = i32::try_from(options.padding_right).unwrap_or(i32::MAX);
+    let mut pen_x = padding_left;
+    // face and glyph advanced last, to space the next glyph from
+    let mut previous: Option<(usize, GlyphKey)> = None;
+    for paragraph in &bidi.paragraphs {
+        let (levels, runs) = bidi.visual_runs(paragraph, paragraph.range.clone());
+        for run in runs {
+            let rtl = levels[run.start].is_rtl();
+            // split the run into the segments drawn with the same face
+            let mut segments: Vec<(Range<usize>, usize)> = Vec::new();
+            for (offset, c) in text[run.clone()].char_indices() {
+                let start = run.start + offset;
+                let end = start + c.len_utf8();
+                match segments.last_mut() {
+                    Some((range, face)) if *face == char_faces[start] => range.end = end,
+                    _ => segments.push((start..end, char_faces[start])),
+                }
+            }
+            if rtl {
+                segments.reverse();
+            }
+
+            for (range, face_index) in segments {
+                let face = &faces.faces[face_index];
+                let shaped =
+                    face.shape(&text[range.clone()], rtl).unwrap_or_else(|| {
+                        let chars = text[range].chars().filter(|&c| !is_invisible(c)).map(|c| {
+                            ShapedGlyph {
+                                key: GlyphKey::Char(c),
+                                advances: true,
+                                space: c.is_whitespace(),
+                                x_offset: 0,
+                                y_offset: 0,
+                            }
+                        });
+                        if rtl {
+                            chars.rev().collect()
+                        } else {
+                            chars.collect()
+                        }
+                    });
+                for glyph in shaped {
+                    if glyph.advances
+                        && let Some((previous_face, previous_key)) = previous
+                    {
+                        pen_x = pen_x.saturating_add(options.letter_spacing);
+                        if options.kerning && previous_face == face_index {
+                            pen_x = pen_x.saturating_add(face.kerning(previous_key, glyph.key));
+                        }
+                    }
+                    glyphs.push(PlacedGlyph {
+                        face: face_index,
+                        key: glyph.key,
+                        x: pen_x.saturating_add(ftpos2pixel(glyph.x_offset + 32)),
+                        y_offset: glyph.y_offset,
+                    });
+                    if glyph.advances {
+                        let advance = face.draw_glyph(glyph.key, None, pen_x, 0)?;
+                        pen_x = pen_x.saturating_add(advance);
+                        if glyph.space {
+                            pen_x = pen_x.saturating_add(options.word_spacing);
+                        }
+                        previous = Some((face_index, glyph.key));
                     }
                 }
             }
         }
+    }
+    Ok((glyphs, pen_x.saturating_add(padding_right).max(0)))
+}
+
+#[test]
+fn test_layout_text() {
+    use crate::badge::bitmap_font::TEST_BDF;
+
+    // the font of the test has only 'A' and ','
+    let mut faces = TextFaces {
+        lib: Library::init().unwrap(),
+        faces: vec![TextFace::Bitmap(Cow::Owned(
+            BitmapFont::from_bdf(TEST_BDF.as_bytes()).unwrap(),
+        ))],
+        fallback_faces: HashMap::new(),
+        builtin_added: true,
+    };
+    let mut layout = |text: &str, options: &TextRenderOptions| {
+        let (glyphs, width) = layout_text(&mut faces, text, &[], 11, options).unwrap();
+        let glyphs = glyphs
+            .iter()
+            .map(|glyph| (glyph.key, glyph.x))
+            .collect::<Vec<_>>();
+        (glyphs, width)
+    };
+    let (a, comma) = (GlyphKey::Char('A'), GlyphKey::Char(','));
+
+    assert_eq!(
+        layout("A,A", &Default::default()),
+        (vec![(a, 0), (comma, 4), (a, 6)], 10)
+    );
+    // the right-to-left run is reversed and the bidi controls are not drawn
+    assert_eq!(
+        layout("A\u{202E}A,\u{202C},", &Default::default()),
+        (vec![(a, 0), (comma, 4), (a, 6), (comma, 10)], 12)
+    );
+    let spaced = TextRenderOptions {
+        letter_spacing: 1,
+        padding_left: 2,
+        padding_right: 3,
+        ..Default::default()
+    };
+    assert_eq!(
+        layout("A,A", &spaced),
+        (vec![(a, 2), (comma, 7), (a, 10)], 17)
+    );
+}
+
+/// Returns true if the text is written from right to left by the first strong character
+pub fn is_right_to_left(text: &str) -> bool {
+    BidiInfo::new(text, None)
+        .paragraphs
+        .first()
+        .is_some_and(|paragraph| paragraph.level.is_rtl())
+}
+
+#[test]
+fn test_is_right_to_left() {
+    assert!(is_right_to_left("שלום"));
+    assert!(is_right_to_left("123 مرحبا"));
+    assert!(!is_right_to_left("Hello שלום"));
+    assert!(!is_right_to_left(""));
+}
 
-        pen_x += ftpos2pixel(glyph.advance().x);
+/// Render text with given font configuration and return the led badge message data.
+///
+/// The font is the built-in font if `font` is `None`. Characters missing in the font are drawn
+/// with the fallback fonts on the same baseline; `font_names` are preferred for them.
+pub(crate) fn render_text(
+    text: &str,
+    pixel_height: usize,
+    font: Option<(&Path, usize)>,
+    font_names: &[&str],
+    options: &TextRenderOptions,
+) -> Result<Vec<u8>, BadgeError> {
+    Ok(canvas2vec(&render_text_to_canvas(
+        text,
+        pixel_height,
+        font,
+        font_names,
+        options,
+    )?))
+}
+
+/// Render text into the bitmap of the exact width
+fn render_text_to_canvas(
+    text: &str,
+    pixel_height: usize,
+    font: Option<(&Path, usize)>,
+    font_names: &[&str],
+    options: &TextRenderOptions,
+) -> Result<Canvas, BadgeError> {
+    let lib = Library::init()?;
+    let face = match font {
+        Some((font_path, font_index)) => TextFace::open(&lib, font_path, font_index, pixel_height)?,
+        None => TextFace::Bitmap(Cow::Borrowed(builtin_font())),
+    };
+    let baseline = face.baseline(pixel_height);
+    let mut faces = TextFaces {
+        lib,
+        faces: vec![face],
+        fallback_faces: HashMap::new(),
+        builtin_added: font.is_none(),
+    };
+
+    let (glyphs, width) = layout_text(&mut faces, text, font_names, pixel_height, options)?;
+    let mut canvas = Canvas::new(width as usize, pixel_height);
+    for glyph in glyphs {
+        faces.faces[glyph.face].draw_glyph(
+            glyph.key,
+            Some(&mut canvas),
+            glyph.x,
+            baseline - glyph.y_offset,
+        )?;
     }
 
-    Ok(canvas2vec(&canvas))
+    Ok(canvas)
 }
 
 #[test]
@@ -153,8 +576,163 @@ fn test_render_text() {
     use crate::badge::font_selector::select_font;
     let (font_path, font_index) = select_font(&["Liberation Sans", "Arial"], Some(10)).unwrap();
 
-    let pixel_data = render_text("Test!", 10, font_path.as_ref(), font_index).unwrap();
+    let pixel_data = render_text(
+        "Test!",
+        10,
+        Some((font_path.as_ref(), font_index)),
+        &[],
+        &TextRenderOptions::default(),
+    )
+    .unwrap();
     assert!(pixel_data.len() > 0);
     assert_eq!(pixel_data.len() % 10, 0);
     assert_eq!(pixel_data.iter().all(|v| *v == 0), false);
 }
+
+#[test]
+fn test_render_text_fallback() {
+    use crate::badge::canvas::vec2canvas;
+
+    // the font of the test has only 'A' and ','
+    let font_path = std::env::temp_dir().join("led-name-badge-test-render-text-fallback.bdf");
+    std::fs::write(&font_path, crate::badge::bitmap_font::TEST_BDF).unwrap();
+    let pixel_data = render_text(
+        "AB",
+        11,
+        Some((&font_path, 0)),
+        &[],
+        &TextRenderOptions::default(),
+    )
+    .unwrap();
+    std::fs::remove_file(&font_path).unwrap();
+
+    let canvas = vec2canvas(&pixel_data, 11);
+    let lit_columns = |columns: std::ops::Range<usize>| {
+        columns
+            .filter(|&x| (0..11).any(|y| canvas.get(x, y)))
+            .count()
+    };
+    assert_eq!(lit_columns(0..4), 3);
+    // 'B' of the fallback font follows
+    assert!(lit_columns(4..canvas.width) > 0);
+
+    let builtin = render_text("AB", 11, None, &[], &TextRenderOptions::default()).unwrap();
+    assert_eq!(builtin.len(), 2 * 11);
+}
+
+#[test]
+fn test_render_text_bidi() {
+    use crate::badge::canvas::vec2canvas;
+
+    // the built-in font is not shaped; the right-to-left run is reversed
+    let render = |text: &str| {
+        vec2canvas(
+            &render_text(text, 11, None, &[], &TextRenderOptions::default()).unwrap(),
+            11,
+        )
+    };
+    let columns = |canvas: &Canvas, x: usize| {
+        (x..x + 6)
+            .map(|x| (0..11).map(|y| canvas.get(x, y)).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    };
+    let rtl = render("a\u{202E}xy\u{202C}b");
+    assert_eq!(columns(&rtl, 0), columns(&render("a"), 0));
+    assert_eq!(columns(&rtl, 6), columns(&render("y"), 0));
+    assert_eq!(columns(&rtl, 12), columns(&render("x"), 0));
+    assert_eq!(columns(&rtl, 18), columns(&render("b"), 0));
+}
+
+#[test]
+#[ignore = "requires DejaVu Sans with the arabic glyphs"]
+fn test_render_text_shaping() {
+    use crate::badge::canvas::vec2canvas;
+    use crate::badge::font_selector::select_font;
+
+    let (font_path, font_index) = select_font(&["DejaVu Sans"], Some(11)).unwrap();
+    let font = Some((font_path.as_ref(), font_index));
+    let render = |text: &str| {
+        vec2canvas(
+            &render_text(text, 11, font, &[], &TextRenderOptions::default()).unwrap(),
+            11,
+        )
+    };
+
+    // the joining forms of the arabic letters differ from the isolated ones
+    let joined = render("بب");
+    let isolated = render("ب\u{200C}ب");
+    assert_ne!(joined.pixels, isolated.pixels);
+    // the combining mark does not advance
+    assert_eq!(render("e\u{0301}").width, render("e").width);
+}
+
+#[test]
+#[ignore = "requires DejaVu Sans with the kerning table"]
+fn test_render_text_kerning() {
+    use crate::badge::font_selector::select_font;
+
+    let (font_path, font_index) = select_font(&["DejaVu Sans"], Some(11)).unwrap();
+    let width = |options: &TextRenderOptions| {
+        render_text_to_canvas("AVAV", 11, Some((&font_path, font_index)), &[], options)
+            .unwrap()
+            .width
+    };
+    let plain = TextRenderOptions {
+        kerning: false,
+        ..Default::default()
+    };
+    // "AV" is kerned by the kerning table of DejaVu Sans
+    assert!(width(&TextRenderOptions::default()) < width(&plain));
+}
+
+#[test]
+fn test_render_text_spacing() {
+    let width = |text: &str, options| {
+        render_text_to_canvas(text, 11, None, &[], &options)
+            .unwrap()
+            .width
+    };
+
+    let plain = TextRenderOptions {
+        kerning: false,
+        ..Default::default()
+    };
+    // the bitmap fonts are not kerned
+    assert_eq!(width("a b", TextRenderOptions::default()), 18);
+    assert_eq!(width("a b", plain.clone()), 18);
+    let letter_spacing = TextRenderOptions {
+        letter_spacing: -1,
+        ..plain.clone()
+    };
+    assert_eq!(width("a b", letter_spacing), 16);
+    let word_spacing = TextRenderOptions {
+        word_spacing: 3,
+        ..plain.clone()
+    };
+    assert_eq!(width("a b", word_spacing), 21);
+    let padding = TextRenderOptions {
+        padding_left: 2,
+        padding_right: 5,
+        ..plain
+    };
+    let canvas = render_text_to_canvas("a", 11, None, &[], &padding).unwrap();
+    assert_eq!(canvas.width, 13);
+    assert!(canvas.is_blank_column(0) && canvas.is_blank_column(1));
+
+    // the layout saturates instead of overflowing
+    let mut faces = TextFaces {
+        lib: Library::init().unwrap(),
+        faces: vec![TextFace::Bitmap(Cow::Borrowed(builtin_font()))],
+        fallback_faces: HashMap::new(),
+        builtin_added: true,
+    };
+    let huge = TextRenderOptions {
+        letter_spacing: i32::MAX,
+        word_spacing: i32::MAX,
+        padding_left: usize::MAX,
+        padding_right: usize::MAX,
+        ..Default::default()
+    };
+    let (_, width) = layout_text(&mut faces, "a b", &[], 11, &huge).unwrap();
+    assert_eq!(width, i32::MAX);
+}
@@ -0,0 +1,121 @@
+use crate::badge::BADGE_MSG_FONT_HEIGHT;
+use crate::badge::canvas::Canvas;
+use crate::badge::image_io::BadgeImageReadError;
+
+/// Place the columns of `height` pixels in the middle of the message height
+fn columns_to_canvas(columns: &[Vec<bool>], height: usize) -> Canvas {
+    let top = (BADGE_MSG_FONT_HEIGHT - height) / 2;
+    let mut canvas = Canvas::new(columns.len(), BADGE_MSG_FONT_HEIGHT);
+    for (x, column) in columns.iter().enumerate() {
+        for (y, &lit) in column.iter().enumerate() {
+            canvas.set(x, top + y, lit);
+        }
+    }
+    canvas
+}
+
+/// Parse the ASCII art of `#` (lit) and `.` (unlit) into the bitmap
+///
+/// The rows are separated by the newlines or `/`, and the shorter rows are padded with
+/// unlit pixels. Art lower than the message is centered vertically.
+pub(crate) fn read_ascii_art(text: &str) -> Result<Canvas, BadgeImageReadError> {
+    let invalid =
+        |message: String| BadgeImageReadError::InvalidImageData(format!("ascii art: {}", message));
+    let rows = text
+        .split(['\n', '/'])
+        .map(|row| row.trim_end_matches('\r'))
+        .skip_while(|row| row.trim().is_empty())
+        .collect::<Vec<_>>();
+    let rows = match rows.iter().rposition(|row| !row.trim().is_empty()) {
+        Some(last) => &rows[..=last],
+        None => return Err(invalid("no rows".to_string())),
+    };
+    if rows.len() > BADGE_MSG_FONT_HEIGHT {
+        return Err(invalid(format!(
+            "{} rows exceed the message height",
+            rows.len()
+        )));
+    }
+
+    let width = rows
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut columns = vec![vec![false; rows.len()]; width];
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            columns[x][y] = match c {
+                '#' => true,
+                '.' | ' ' => false,
+                c => return Err(invalid(format!("wrong character '{}'", c))),
+            };
+        }
+    }
+    Ok(columns_to_canvas(&columns, rows.len()))
+}
+
+#[test]
+fn test_read_ascii_art() {
+    let canvas = read_ascii_art("\n.#.\n#.#\n##\n").unwrap();
+    assert_eq!((canvas.width, canvas.height), (3, BADGE_MSG_FONT_HEIGHT));
+    // 3 rows from the 5th row
+    #[rustfmt::skip]
+    assert_eq!(canvas.pixels[4 * 3..7 * 3], [
+        0, 1, 0,
+        1, 0, 1,
+        1, 1, 0,
+    ]);
+    assert_eq!(canvas.pixels.iter().filter(|&&v| v != 0).count(), 5);
+
+    assert_eq!(read_ascii_art(".#./#.#/##").unwrap(), canvas);
+    assert!(read_ascii_art("#x#").is_err());
+    assert!(read_ascii_art(" \n").is_err());
+    assert!(read_ascii_art(&"#\n".repeat(BADGE_MSG_FONT_HEIGHT + 1)).is_err());
+}
+
+/// Parse the hex values of the columns into the bitmap
+///
+/// The values are separated by the whitespaces or the commas and may have the `0x` prefix.
+/// The bit 10 (`0x400`) is the top row and the bit 0 is the bottom row of the message.
+pub(crate) fn read_hex_columns(text: &str) -> Result<Canvas, BadgeImageReadError> {
+    let invalid = |token: &str| {
+        BadgeImageReadError::InvalidImageData(format!("hex columns: wrong column '{}'", token))
+    };
+    let columns = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            let digits = token
+                .strip_prefix("0x")
+                .or_else(|| token.strip_prefix("0X"))
+                .unwrap_or(token);
+            match u16::from_str_radix(digits, 16) {
+                Ok(v) if v < 1 << BADGE_MSG_FONT_HEIGHT => Ok((0..BADGE_MSG_FONT_HEIGHT)
+                    .rev()
+                    .map(|bit| v & (1 << bit) != 0)
+                    .collect::<Vec<_>>()),
+                _ => Err(invalid(token)),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if columns.is_empty() {
+        return Err(BadgeImageReadError::InvalidImageData(
+            "hex columns: no columns".to_string(),
+        ));
+    }
+    Ok(columns_to_canvas(&columns, BADGE_MSG_FONT_HEIGHT))
+}
+
+#[test]
+fn test_read_hex_columns() {
+    let canvas = read_hex_columns("0x401, 7ff\n000").unwrap();
+    assert_eq!((canvas.width, canvas.height), (3, BADGE_MSG_FONT_HEIGHT));
+    assert!(canvas.get(0, 0) && canvas.get(0, BADGE_MSG_FONT_HEIGHT - 1));
+    assert!(!canvas.get(0, 1));
+    assert!((0..BADGE_MSG_FONT_HEIGHT).all(|y| canvas.get(1, y) && !canvas.get(2, y)));
+
+    assert!(read_hex_columns("800").is_err());
+    assert!(read_hex_columns("0x").is_err());
+    assert!(read_hex_columns(" ").is_err());
+}
@@ -1,3 +1,12 @@
+#![cfg_attr(
+    test,
+    allow(
+        clippy::bool_assert_comparison,
+        clippy::len_zero,
+        clippy::redundant_pattern_matching
+    )
+)]
+
 extern crate hidapi;
 
 use std::fs::File;
@@ -7,12 +16,19 @@ use std::str::FromStr;
 
 use crate::arg_parser::{App, Arg, ArgParseError, ArgValue};
 use crate::badge::device::BadgeType;
-use crate::badge::{BADGE_BRIGHTNESS_RANGE, BADGE_SPEED_RANGE, Badge, BadgeEffect, BadgeError};
+use crate::badge::{
+    AnimationFormat, BADGE_BRIGHTNESS_RANGE, BADGE_SPEED_RANGE, Badge, BadgeAlignment, BadgeEffect,
+    BadgeError, Canvas, Color, DitherMethod, DotShape, GraphicsProtocol, GrayChannel, ImageGrid,
+    ImageImportOptions, ImageRegion, LedImageFormat, LedRenderOptions, MAX_MESSAGE_WIDTH,
+    MessageFormat, N_MESSAGES, PreviewStyle, TextRenderOptions, is_right_to_left,
+    read_image_grid_frames, read_png_frame,
+};
 
 mod arg_parser;
 mod badge;
 
 #[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 enum CliError {
     #[error(transparent)]
     ArgParseError(#[from] ArgParseError),
@@ -29,13 +45,57 @@ enum CliArgumentId {
     t,
     T,
     F,
+    no_kerning,
+    letter_spacing,
+    word_spacing,
+    padding_left,
+    padding_right,
     p,
+    A,
+    g,
     s,
     e,
     b,
     f,
     B,
+    a,
+    w,
+    d,
     o,
+    S,
+    step,
+    P,
+    play,
+    G,
+    scale,
+    color,
+    L,
+    dot,
+    gap,
+    unlit_color,
+    bezel,
+    H,
+    O,
+    background,
+    fit_width,
+    dither,
+    threshold,
+    invert,
+    alpha_mask,
+    channel,
+    region,
+    grid,
+    raw,
+    hex,
+    raw_width,
+    ascii_art,
+    hex_columns,
+    format,
+    snap_strokes,
+    no_antialias,
+    split,
+    split_at_blank,
+    rtl_scroll,
     h,
 }
 
@@ -63,13 +123,169 @@ fn parse_arguments() -> Result<Box<[ArgValue<CliArgumentId>]>, ArgParseError> {
             CliArgumentId::F,
             'F',
             Some("font".to_string()),
-            "Font family name or font file path".to_string(),
+            "Font family name or font file path\n(bdf and pcf fonts are drawn pixel-for-pixel,\n'builtin' selects the built-in 6x12 font)"
+                .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::no_kerning,
+            "no-kerning",
+            None,
+            "Do not apply the kerning of the font to text messages".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::letter_spacing,
+            "letter-spacing",
+            Some("columns".to_string()),
+            "Columns added between the letters of text messages\n(negative to narrow)".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::word_spacing,
+            "word-spacing",
+            Some("columns".to_string()),
+            "Columns added to the spaces of text messages".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::padding_left,
+            "padding-left",
+            Some("columns".to_string()),
+            "Blank columns before text messages".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::padding_right,
+            "padding-right",
+            Some("columns".to_string()),
+            "Blank columns after text messages".to_string(),
         ),
         Arg::new(
             CliArgumentId::p,
             'p',
             Some("file".to_string()),
-            "Load message png file".to_string(),
+            "Load message image file\n(png, gif, bmp, pbm/pgm, xbm, svg)".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::background,
+            "background",
+            Some("#rrggbb".to_string()),
+            "Color under transparent pixels of loaded png files\n(default: #000000)".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::fit_width,
+            "fit-width",
+            Some("pixels".to_string()),
+            "Fit loaded png files in the width keeping aspect ratio".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::dither,
+            "dither",
+            Some("method".to_string()),
+            format!(
+                "Conversion of loaded png files to monochrome\n[{}]",
+                DitherMethod::values()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::threshold,
+            "threshold",
+            Some("level".to_string()),
+            "Gray level [0..255] from which pixels of loaded\nimage files are lit (default: 128)"
+                .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::invert,
+            "invert",
+            None,
+            "Light up dark pixels of loaded image files".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::alpha_mask,
+            "alpha-mask",
+            None,
+            "Light up opaque pixels of loaded image files".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::channel,
+            "channel",
+            Some("channel".to_string()),
+            format!(
+                "Channel or luma formula of loaded image files\n[{}]",
+                GrayChannel::values()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )
+            .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::region,
+            "region",
+            Some("x,y,w,h".to_string()),
+            "Load only the rectangle of image files".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::grid,
+            "grid",
+            Some("CxR".to_string()),
+            "Slice image files into grid cells loaded to\nconsecutive slots by the following -p or\nframes by the following -A"
+                .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::raw,
+            "raw",
+            Some("file".to_string()),
+            "Load message data of 11 bytes per 8 columns".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::hex,
+            "hex",
+            Some("file".to_string()),
+            "Load message data of --raw written in hex text".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::raw_width,
+            "raw-width",
+            Some("columns".to_string()),
+            "Width of message data loaded by the following\n--raw and --hex (default: length of the data)"
+                .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::ascii_art,
+            "ascii-art",
+            Some("art".to_string()),
+            "Load message drawn with # and . in rows separated by /\n(or file of rows)".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::hex_columns,
+            "hex-columns",
+            Some("hex".to_string()),
+            "Load message of hex columns, 400 is the top row\n(or file of them)".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::snap_strokes,
+            "snap-strokes",
+            None,
+            "Round stroke widths of loaded svg files to pixels".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::no_antialias,
+            "no-antialias",
+            None,
+            "Render loaded svg files without antialiasing".to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::A,
+            'A',
+            Some("file".to_string()),
+            "Add animation frame png file\n(directory: all png files in name order)".to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::g,
+            'g',
+            Some("file".to_string()),
+            "Load animated gif/png file as animation".to_string(),
         ),
         Arg::new(
             CliArgumentId::s,
@@ -90,6 +306,13 @@ fn parse_arguments() -> Result<Box<[ArgValue<CliArgumentId>]>, ArgParseError> {
             )
             .to_string(),
         ),
+        Arg::new_long(
+            CliArgumentId::rtl_scroll,
+            "rtl-scroll",
+            None,
+            "Scroll right-to-left text messages to the right\n(unless the effect is specified)"
+                .to_string(),
+        ),
         Arg::new(CliArgumentId::b, 'b', None, "Blink message".to_string()),
         Arg::new(
             CliArgumentId::f,
@@ -103,11 +326,174 @@ fn parse_arguments() -> Result<Box<[ArgValue<CliArgumentId>]>, ArgParseError> {
             Some("brightness".to_string()),
             "LED brightness [0..3] (B1248: unused)".to_string(),
         ),
+        Arg::new(
+            CliArgumentId::a,
+            'a',
+            Some("align".to_string()),
+            format!(
+                "Align message horizontally\n[{}]",
+                BadgeAlignment::values()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::w,
+            'w',
+            Some("width".to_string()),
+            "Width to align message within by the following -a\n(default: visible width of the device)".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::split,
+            "split",
+            Some("width".to_string()),
+            "Split message into slots from the message number\n(effect and speed are shared)"
+                .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::split_at_blank,
+            "split-at-blank",
+            None,
+            "Split messages at blank columns where possible".to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::d,
+            'd',
+            Some("device".to_string()),
+            format!(
+                "Badge device type\n[{}]",
+                BadgeType::values()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .to_string(),
+        ),
         Arg::new(
             CliArgumentId::o,
             'o',
+            Some("file".to_string()),
+            "Write to png/pbm/xbm/h/rs/json/bin/hex file instead of badge".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::format,
+            "format",
+            Some("format".to_string()),
+            format!(
+                "File format of -o instead of the file extension\n[{}]",
+                MessageFormat::values()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::S,
+            'S',
+            Some("file".to_string()),
+            "Write simulated display animation to gif/png file\ninstead of badge".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::step,
+            "step",
+            Some("msec".to_string()),
+            "Time step of the simulated animation (default: 50)".to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::P,
+            'P',
+            Some("style".to_string()),
+            format!(
+                "Preview message in terminal instead of badge\n[{}]",
+                PreviewStyle::values()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::play,
+            "play",
+            Some("style".to_string()),
+            "Play simulated display animation in terminal\ninstead of badge".to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::G,
+            'G',
+            Some("protocol".to_string()),
+            format!(
+                "Preview message as LED image in terminal\ninstead of badge\n[auto,{}]",
+                GraphicsProtocol::values()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::scale,
+            "scale",
+            Some("pixels".to_string()),
+            "Size of a LED of the LED-look image (default: 8)".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::color,
+            "color",
+            Some("#rrggbb".to_string()),
+            "Color of the lit LED of the LED-look image".to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::L,
+            'L',
+            Some("file".to_string()),
+            "Write LED-look image to png/svg file instead of badge".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::dot,
+            "dot",
+            Some("shape".to_string()),
+            format!(
+                "Shape of the LED of the LED-look image (default: round)\n[{}]",
+                DotShape::values()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::gap,
+            "gap",
+            Some("pixels".to_string()),
+            "Gap between the LEDs of the LED-look image (default: 1)".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::unlit_color,
+            "unlit-color",
+            Some("#rrggbb".to_string()),
+            "Color of the unlit LED of the LED-look image".to_string(),
+        ),
+        Arg::new_long(
+            CliArgumentId::bezel,
+            "bezel",
+            Some("pixels".to_string()),
+            "Width of the bezel of the LED-look image (default: 0)".to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::H,
+            'H',
+            Some("htmlfile".to_string()),
+            "Write html page playing all messages instead of badge".to_string(),
+        ),
+        Arg::new(
+            CliArgumentId::O,
+            'O',
             Some("pngfile".to_string()),
-            "Write to png file instead of badge".to_string(),
+            "Write all messages to png sprite sheet and json\nfile instead of badge".to_string(),
         ),
         Arg::new(
             CliArgumentId::h,
@@ -121,12 +507,14 @@ fn parse_arguments() -> Result<Box<[ArgValue<CliArgumentId>]>, ArgParseError> {
     let app = App::new(&options);
     let values = app.parse(&arguments)?;
 
-    if values.iter().any(|option| match option {
-        ArgValue::Arg {
-            id: CliArgumentId::h,
-            ..
-        } => true,
-        _ => false,
+    if values.iter().any(|option| {
+        matches!(
+            option,
+            ArgValue::Arg {
+                id: CliArgumentId::h,
+                ..
+            }
+        )
     }) {
         println!(
             "{}\n\nUSAGE:\n    {} [OPTIONS]\n\nOPTIONS:\n{}",
@@ -140,6 +528,92 @@ fn parse_arguments() -> Result<Box<[ArgValue<CliArgumentId>]>, ArgParseError> {
     }
 }
 
+/// Message settings given on the command line, which take precedence over the png metadata
+#[derive(Debug, Default, Copy, Clone)]
+struct MessageOverrides {
+    effect: Option<BadgeEffect>,
+    speed: Option<u8>,
+    blink: bool,
+    frame: bool,
+}
+
+impl MessageOverrides {
+    /// Set the settings given on the command line again
+    fn apply(&self, badge: &mut Badge, msg_number: usize) -> Result<(), BadgeError> {
+        if let Some(effect) = self.effect {
+            badge.set_effect_pattern(msg_number, effect)?;
+        }
+        if let Some(speed) = self.speed {
+            badge.set_effect_speed(msg_number, speed)?;
+        }
+        if self.blink {
+            badge.set_effect_blink(msg_number, true)?;
+        }
+        if self.frame {
+            badge.set_effect_frame(msg_number, true)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the value of the preview style option
+fn parse_preview_style(option_name: &str, value: &str) -> Result<PreviewStyle, CliError> {
+    PreviewStyle::from_str(value).map_err(|_err| {
+        CliError::CliError(format!(
+            "{} '{}': wrong value. specify [{}]",
+            option_name,
+            value,
+            PreviewStyle::values()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        ))
+    })
+}
+
+/// Read animation frames from a png file or all png files in a directory
+fn read_frames(path: &Path, options: &ImageImportOptions) -> Result<Vec<Canvas>, BadgeError> {
+    let to_file_io_error = |e| BadgeError::FileIo(Some(path.display().to_string()), e);
+
+    let paths = if path.is_dir() {
+        let mut paths = std::fs::read_dir(path)
+            .map_err(to_file_io_error)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(to_file_io_error)?;
+        paths.retain(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+        });
+        paths.sort();
+        paths
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    paths
+        .iter()
+        .map(|path| {
+            let path_info = Some(path.display().to_string());
+            let file = File::open(path).map_err(|e| BadgeError::FileIo(path_info.clone(), e))?;
+            read_png_frame(BufReader::new(&file), options).map_err(|e| match e {
+                BadgeError::PngReadError(None, e) => BadgeError::PngReadError(path_info, e),
+                e => e,
+            })
+        })
+        .collect()
+}
+
+/// Read the file if the value names one, otherwise the value is the content itself
+fn read_inline_or_file(value: &str) -> Result<String, BadgeError> {
+    let path = Path::new(value);
+    if path.is_file() {
+        std::fs::read_to_string(path).map_err(|e| BadgeError::FileIo(Some(value.to_string()), e))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
 /// CLI entry point
 fn main() {
     (|| -> Result<i32, CliError> {
@@ -148,6 +622,27 @@ fn main() {
         let mut badge = Badge::new()?;
         let mut msg_number = 0;
         let mut disable_send_to_badge = false;
+        let mut badge_type = BadgeType::Auto;
+        let mut align_width = None;
+        // -w given after the last -a, which would be ignored
+        let mut unused_align_width = None;
+        let mut split_at_blank = false;
+        let mut rtl_scroll = false;
+        let mut animation_frames: [Vec<Canvas>; N_MESSAGES] = Default::default();
+        let mut overrides = [MessageOverrides::default(); N_MESSAGES];
+        let mut import_options = ImageImportOptions::default();
+        let mut text_options = TextRenderOptions::default();
+        let mut grid = None;
+        // --grid given after the last -p or -A, which would be ignored
+        let mut unused_grid = None;
+        let mut raw_width = None;
+        // --raw-width given after the last --raw or --hex, which would be ignored
+        let mut unused_raw_width = None;
+        let mut message_format = None;
+        let mut simulation_step = 50;
+        let mut led_render_options = LedRenderOptions::default();
+        // the unlit color follows --color unless given by --unlit-color
+        let mut unlit_color_given = false;
         let mut font_family = Vec::with_capacity(1);
         const DEFAULT_FONT_FAMILY: [&str; 2] = ["Liberation Sans", "Arial"];
 
@@ -177,7 +672,12 @@ fn main() {
                         font_family.as_ref()
                     };
 
-                    badge.add_text_message(msg_number, value.as_ref().unwrap(), font_names)?;
+                    let msg = value.as_ref().unwrap();
+                    badge.add_text_message(msg_number, msg, font_names, &text_options)?;
+                    if rtl_scroll && overrides[msg_number].effect.is_none() && is_right_to_left(msg)
+                    {
+                        badge.set_effect_pattern(msg_number, BadgeEffect::Right)?;
+                    }
                 }
                 Arg {
                     id: CliArgumentId::T,
@@ -197,7 +697,13 @@ fn main() {
                         font_family.as_ref()
                     };
 
-                    badge.add_text_message(msg_number, msg.as_str(), font_names)?;
+                    badge.add_text_message(msg_number, msg.as_str(), font_names, &text_options)?;
+                    if rtl_scroll
+                        && overrides[msg_number].effect.is_none()
+                        && is_right_to_left(&msg)
+                    {
+                        badge.set_effect_pattern(msg_number, BadgeEffect::Right)?;
+                    }
                 }
                 Arg {
                     id: CliArgumentId::F,
@@ -208,6 +714,60 @@ fn main() {
                     }
                     font_family.push(value.as_ref().unwrap().as_str());
                 }
+                Arg {
+                    id: CliArgumentId::no_kerning,
+                    value: _,
+                } => {
+                    text_options.kerning = false;
+                }
+                Arg {
+                    id: id @ (CliArgumentId::letter_spacing | CliArgumentId::word_spacing),
+                    value,
+                } => {
+                    const MAX_SPACING: i32 = MAX_MESSAGE_WIDTH as i32;
+                    let spacing = match i32::from_str(value.as_ref().unwrap().as_str()) {
+                        Ok(i) if (-MAX_SPACING..=MAX_SPACING).contains(&i) => Ok(i),
+                        _ => Err(CliError::CliError(format!(
+                            "--{} '{}': wrong value. specify -{}..{}",
+                            if *id == CliArgumentId::letter_spacing {
+                                "letter-spacing"
+                            } else {
+                                "word-spacing"
+                            },
+                            value.as_ref().unwrap(),
+                            MAX_SPACING,
+                            MAX_SPACING,
+                        ))),
+                    }?;
+                    if *id == CliArgumentId::letter_spacing {
+                        text_options.letter_spacing = spacing;
+                    } else {
+                        text_options.word_spacing = spacing;
+                    }
+                }
+                Arg {
+                    id: id @ (CliArgumentId::padding_left | CliArgumentId::padding_right),
+                    value,
+                } => {
+                    let padding = match usize::from_str(value.as_ref().unwrap().as_str()) {
+                        Ok(i) if i <= MAX_MESSAGE_WIDTH => Ok(i),
+                        _ => Err(CliError::CliError(format!(
+                            "--{} '{}': wrong value. specify 0..{}",
+                            if *id == CliArgumentId::padding_left {
+                                "padding-left"
+                            } else {
+                                "padding-right"
+                            },
+                            value.as_ref().unwrap(),
+                            MAX_MESSAGE_WIDTH,
+                        ))),
+                    }?;
+                    if *id == CliArgumentId::padding_left {
+                        text_options.padding_left = padding;
+                    } else {
+                        text_options.padding_right = padding;
+                    }
+                }
                 Arg {
                     id: CliArgumentId::p,
                     value,
@@ -215,7 +775,247 @@ fn main() {
                     let file = File::open(Path::new(&value.as_ref().unwrap()))
                         .map_err(|e| CliError::BadgeError(BadgeError::FileIo(value.clone(), e)))?;
                     let reader = BufReader::new(&file);
-                    badge.add_png_message(msg_number, reader)?;
+                    let map_error = |e| match e {
+                        BadgeError::PngReadError(None, e) => {
+                            BadgeError::PngReadError(value.clone(), e)
+                        }
+                        BadgeError::ImageReadError(None, e) => {
+                            BadgeError::ImageReadError(value.clone(), e)
+                        }
+                        e => e,
+                    };
+                    if let Some(grid) = grid {
+                        badge
+                            .add_image_grid_messages(msg_number, reader, grid, &import_options)
+                            .map_err(map_error)?;
+                        unused_grid = None;
+                    } else {
+                        badge
+                            .add_image_message(msg_number, reader, &import_options)
+                            .map_err(map_error)?;
+                        overrides[msg_number].apply(&mut badge, msg_number)?;
+                    }
+                }
+                Arg {
+                    id: CliArgumentId::threshold,
+                    value,
+                } => {
+                    import_options.threshold = u8::from_str(value.as_ref().unwrap().as_str())
+                        .map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--threshold '{}': wrong value. specify [0..255]",
+                                value.as_ref().unwrap()
+                            ))
+                        })?;
+                }
+                Arg {
+                    id: CliArgumentId::invert,
+                    value: _,
+                } => {
+                    import_options.invert = true;
+                }
+                Arg {
+                    id: CliArgumentId::alpha_mask,
+                    value: _,
+                } => {
+                    import_options.alpha_mask = true;
+                }
+                Arg {
+                    id: CliArgumentId::channel,
+                    value,
+                } => {
+                    import_options.channel = GrayChannel::from_str(value.as_ref().unwrap())
+                        .map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--channel '{}': wrong value. specify [{}]",
+                                value.as_ref().unwrap(),
+                                GrayChannel::values()
+                                    .map(|v| v.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(","),
+                            ))
+                        })?;
+                }
+                Arg {
+                    id: id @ (CliArgumentId::raw | CliArgumentId::hex),
+                    value,
+                } => {
+                    let file = File::open(Path::new(&value.as_ref().unwrap()))
+                        .map_err(|e| CliError::BadgeError(BadgeError::FileIo(value.clone(), e)))?;
+                    let reader = BufReader::new(&file);
+                    unused_raw_width = None;
+                    if *id == CliArgumentId::hex {
+                        badge.add_hex_message(msg_number, reader, raw_width)
+                    } else {
+                        badge.add_raw_message(msg_number, reader, raw_width)
+                    }
+                    .map_err(|e| match e {
+                        BadgeError::ImageReadError(None, e) => {
+                            BadgeError::ImageReadError(value.clone(), e)
+                        }
+                        e => e,
+                    })?;
+                }
+                Arg {
+                    id: CliArgumentId::ascii_art,
+                    value,
+                } => {
+                    let art = read_inline_or_file(value.as_ref().unwrap())?;
+                    badge.add_ascii_art_message(msg_number, &art)?;
+                }
+                Arg {
+                    id: CliArgumentId::hex_columns,
+                    value,
+                } => {
+                    let columns = read_inline_or_file(value.as_ref().unwrap())?;
+                    badge.add_hex_columns_message(msg_number, &columns)?;
+                }
+                Arg {
+                    id: CliArgumentId::raw_width,
+                    value,
+                } => {
+                    raw_width = match usize::from_str(value.as_ref().unwrap().as_str()) {
+                        Ok(i) if i > 0 => Ok(Some(i)),
+                        _ => Err(CliError::CliError(format!(
+                            "--raw-width '{}': wrong value. specify number of columns",
+                            value.as_ref().unwrap()
+                        ))),
+                    }?;
+                    unused_raw_width = value.as_deref();
+                }
+                Arg {
+                    id: CliArgumentId::region,
+                    value,
+                } => {
+                    import_options.region = Some(
+                        ImageRegion::from_str(value.as_ref().unwrap()).map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--region '{}': wrong value. specify x,y,width,height",
+                                value.as_ref().unwrap()
+                            ))
+                        })?,
+                    );
+                }
+                Arg {
+                    id: CliArgumentId::grid,
+                    value,
+                } => {
+                    grid = Some(
+                        ImageGrid::from_str(value.as_ref().unwrap()).map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--grid '{}': wrong value. specify columns x rows such as 4x2",
+                                value.as_ref().unwrap()
+                            ))
+                        })?,
+                    );
+                    unused_grid = value.as_deref();
+                }
+                Arg {
+                    id: CliArgumentId::background,
+                    value,
+                } => {
+                    import_options.background =
+                        Color::from_str(value.as_ref().unwrap()).map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--background '{}': wrong value. specify #rrggbb",
+                                value.as_ref().unwrap()
+                            ))
+                        })?;
+                }
+                Arg {
+                    id: CliArgumentId::fit_width,
+                    value,
+                } => {
+                    import_options.max_width =
+                        match usize::from_str(value.as_ref().unwrap().as_str()) {
+                            Ok(i) if i > 0 => Ok(Some(i)),
+                            _ => Err(CliError::CliError(format!(
+                                "--fit-width '{}': wrong value. specify pixels",
+                                value.as_ref().unwrap()
+                            ))),
+                        }?;
+                }
+                Arg {
+                    id: CliArgumentId::dither,
+                    value,
+                } => {
+                    import_options.dither = DitherMethod::from_str(value.as_ref().unwrap())
+                        .map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--dither '{}': wrong value. specify [{}]",
+                                value.as_ref().unwrap(),
+                                DitherMethod::values()
+                                    .map(|v| v.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(","),
+                            ))
+                        })?;
+                }
+                Arg {
+                    id: CliArgumentId::snap_strokes,
+                    value: _,
+                } => {
+                    import_options.snap_strokes = true;
+                }
+                Arg {
+                    id: CliArgumentId::no_antialias,
+                    value: _,
+                } => {
+                    import_options.antialias = false;
+                }
+                Arg {
+                    id: CliArgumentId::A,
+                    value,
+                } => {
+                    let frames = if let Some(grid) = grid {
+                        unused_grid = None;
+                        let file =
+                            File::open(Path::new(&value.as_ref().unwrap())).map_err(|e| {
+                                CliError::BadgeError(BadgeError::FileIo(value.clone(), e))
+                            })?;
+                        read_image_grid_frames(BufReader::new(&file), grid, &import_options)
+                            .map_err(|e| match e {
+                                BadgeError::ImageReadError(None, e) => {
+                                    BadgeError::ImageReadError(value.clone(), e)
+                                }
+                                e => e,
+                            })?
+                    } else {
+                        read_frames(Path::new(value.as_ref().unwrap()), &import_options)?
+                    };
+                    if frames.is_empty() {
+                        return Err(CliError::CliError(format!(
+                            "-A '{}': no png file found",
+                            value.as_ref().unwrap()
+                        )));
+                    }
+                    animation_frames[msg_number].extend(frames);
+                    badge.add_frames_message(
+                        msg_number,
+                        &animation_frames[msg_number],
+                        badge_type.visible_width(),
+                    )?;
+                }
+                Arg {
+                    id: CliArgumentId::g,
+                    value,
+                } => {
+                    let file = File::open(Path::new(&value.as_ref().unwrap()))
+                        .map_err(|e| CliError::BadgeError(BadgeError::FileIo(value.clone(), e)))?;
+                    let reader = BufReader::new(&file);
+                    badge
+                        .add_animation_message(
+                            msg_number,
+                            reader,
+                            badge_type.visible_width(),
+                            &import_options,
+                        )
+                        .map_err(|e| match e {
+                            BadgeError::ImageReadError(None, e) => {
+                                BadgeError::ImageReadError(value.clone(), e)
+                            }
+                            e => e,
+                        })?;
                 }
                 Arg {
                     id: CliArgumentId::s,
@@ -229,6 +1029,7 @@ fn main() {
                         ))),
                     }?;
                     badge.set_effect_speed(msg_number, msg_speed)?;
+                    overrides[msg_number].speed = Some(msg_speed);
                 }
                 Arg {
                     id: CliArgumentId::e,
@@ -246,18 +1047,21 @@ fn main() {
                             ))
                         })?;
                     badge.set_effect_pattern(msg_number, msg_effect)?;
+                    overrides[msg_number].effect = Some(msg_effect);
                 }
                 Arg {
                     id: CliArgumentId::b,
                     value: _,
                 } => {
                     badge.set_effect_blink(msg_number, true)?;
+                    overrides[msg_number].blink = true;
                 }
                 Arg {
                     id: CliArgumentId::f,
                     value: _,
                 } => {
                     badge.set_effect_frame(msg_number, true)?;
+                    overrides[msg_number].frame = true;
                 }
                 Arg {
                     id: CliArgumentId::B,
@@ -272,14 +1076,383 @@ fn main() {
                     }?;
                     badge.set_brightness(msg_brightness)?;
                 }
+                Arg {
+                    id: CliArgumentId::a,
+                    value,
+                } => {
+                    let msg_alignment = BadgeAlignment::from_str(value.as_ref().unwrap().as_str())
+                        .map_err(|_err| {
+                            CliError::CliError(format!(
+                                "-a '{}': wrong value. specify [{}]",
+                                value.as_ref().unwrap(),
+                                BadgeAlignment::values()
+                                    .map(|v| v.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(","),
+                            ))
+                        })?;
+                    let width = align_width.unwrap_or_else(|| badge_type.visible_width());
+                    badge.align_message(msg_number, msg_alignment, width)?;
+                    unused_align_width = None;
+                }
+                Arg {
+                    id: CliArgumentId::w,
+                    value,
+                } => {
+                    align_width = match usize::from_str(value.as_ref().unwrap().as_str()) {
+                        Ok(i) if (1..=MAX_MESSAGE_WIDTH).contains(&i) => Ok(Some(i)),
+                        _ => Err(CliError::CliError(format!(
+                            "-w '{}': wrong value. specify 1..{}",
+                            value.as_ref().unwrap(),
+                            MAX_MESSAGE_WIDTH,
+                        ))),
+                    }?;
+                    unused_align_width = value.as_deref();
+                }
+                Arg {
+                    id: CliArgumentId::split,
+                    value,
+                } => {
+                    let width = match usize::from_str(value.as_ref().unwrap().as_str()) {
+                        Ok(i) if i > 0 => Ok(i),
+                        _ => Err(CliError::CliError(format!(
+                            "--split '{}': wrong value. specify number of columns",
+                            value.as_ref().unwrap()
+                        ))),
+                    }?;
+                    let count = badge.split_message(msg_number, width, split_at_blank)?;
+                    for i in 1..count {
+                        overrides[msg_number + i] = overrides[msg_number];
+                    }
+                }
+                Arg {
+                    id: CliArgumentId::split_at_blank,
+                    value: _,
+                } => {
+                    split_at_blank = true;
+                }
+                Arg {
+                    id: CliArgumentId::rtl_scroll,
+                    value: _,
+                } => {
+                    rtl_scroll = true;
+                }
+                Arg {
+                    id: CliArgumentId::d,
+                    value,
+                } => {
+                    badge_type =
+                        BadgeType::from_str(value.as_ref().unwrap().as_str()).map_err(|_err| {
+                            CliError::CliError(format!(
+                                "-d '{}': wrong value. specify [{}]",
+                                value.as_ref().unwrap(),
+                                BadgeType::values()
+                                    .map(|v| v.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(","),
+                            ))
+                        })?;
+                }
                 Arg {
                     id: CliArgumentId::o,
                     value,
+                } => {
+                    let path = Path::new(value.as_ref().unwrap());
+                    let format = message_format
+                        .or_else(|| {
+                            path.extension().and_then(|ext| {
+                                MessageFormat::from_extension(&ext.to_string_lossy())
+                            })
+                        })
+                        .unwrap_or(MessageFormat::Png);
+                    let file = File::create(path)
+                        .map_err(|e| CliError::BadgeError(BadgeError::FileIo(value.clone(), e)))?;
+                    let writer = BufWriter::new(&file);
+                    badge
+                        .write_message(msg_number, format, writer)
+                        .map_err(|e| match e {
+                            BadgeError::PngWriteError(None, e) => {
+                                BadgeError::PngWriteError(value.clone(), e)
+                            }
+                            BadgeError::ImageWriteError(None, e) => {
+                                BadgeError::ImageWriteError(value.clone(), e)
+                            }
+                            e => e,
+                        })?;
+                    disable_send_to_badge = true;
+                }
+                Arg {
+                    id: CliArgumentId::format,
+                    value,
+                } => {
+                    message_format = Some(
+                        MessageFormat::from_str(value.as_ref().unwrap()).map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--format '{}': wrong value. specify [{}]",
+                                value.as_ref().unwrap(),
+                                MessageFormat::values()
+                                    .map(|v| v.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(","),
+                            ))
+                        })?,
+                    );
+                }
+                Arg {
+                    id: CliArgumentId::S,
+                    value,
+                } => {
+                    let path = Path::new(value.as_ref().unwrap());
+                    let format = if path
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"))
+                    {
+                        AnimationFormat::Gif
+                    } else {
+                        AnimationFormat::Png
+                    };
+                    let file = File::create(path)
+                        .map_err(|e| CliError::BadgeError(BadgeError::FileIo(value.clone(), e)))?;
+                    let writer = BufWriter::new(&file);
+                    badge
+                        .write_simulation(
+                            badge_type.visible_width(),
+                            simulation_step,
+                            format,
+                            writer,
+                        )
+                        .map_err(|e| match e {
+                            BadgeError::ImageWriteError(None, e) => {
+                                BadgeError::ImageWriteError(value.clone(), e)
+                            }
+                            e => e,
+                        })?;
+                    disable_send_to_badge = true;
+                }
+                Arg {
+                    id: CliArgumentId::step,
+                    value,
+                } => {
+                    simulation_step = match u32::from_str(value.as_ref().unwrap().as_str()) {
+                        Ok(i) if i > 0 => Ok(i),
+                        _ => Err(CliError::CliError(format!(
+                            "--step '{}': wrong value. specify milliseconds",
+                            value.as_ref().unwrap()
+                        ))),
+                    }?;
+                }
+                Arg {
+                    id: CliArgumentId::P,
+                    value,
+                } => {
+                    let style = parse_preview_style("-P", value.as_ref().unwrap())?;
+                    badge.write_preview(msg_number, style, std::io::stdout())?;
+                    disable_send_to_badge = true;
+                }
+                Arg {
+                    id: CliArgumentId::play,
+                    value,
+                } => {
+                    let style = parse_preview_style("--play", value.as_ref().unwrap())?;
+                    badge.play_preview(
+                        badge_type.visible_width(),
+                        simulation_step,
+                        style,
+                        std::io::stdout(),
+                    )?;
+                    disable_send_to_badge = true;
+                }
+                Arg {
+                    id: CliArgumentId::G,
+                    value,
+                } => {
+                    let value = value.as_ref().unwrap();
+                    let protocol = if value == "auto" {
+                        GraphicsProtocol::detect()
+                    } else {
+                        Some(GraphicsProtocol::from_str(value).map_err(|_err| {
+                            CliError::CliError(format!(
+                                "-G '{}': wrong value. specify [auto,{}]",
+                                value,
+                                GraphicsProtocol::values()
+                                    .map(|v| v.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(","),
+                            ))
+                        })?)
+                    };
+                    match protocol {
+                        Some(protocol) => badge.write_graphics_preview(
+                            msg_number,
+                            protocol,
+                            &led_render_options,
+                            std::io::stdout(),
+                        )?,
+                        // fall back to the text preview on terminals without graphics
+                        None => badge.write_preview(
+                            msg_number,
+                            PreviewStyle::HalfBlock,
+                            std::io::stdout(),
+                        )?,
+                    }
+                    disable_send_to_badge = true;
+                }
+                Arg {
+                    id: CliArgumentId::scale,
+                    value,
+                } => {
+                    led_render_options.scale =
+                        match usize::from_str(value.as_ref().unwrap().as_str()) {
+                            Ok(i) if (1..=64).contains(&i) => Ok(i),
+                            _ => Err(CliError::CliError(format!(
+                                "--scale '{}': wrong value. specify 1..64",
+                                value.as_ref().unwrap()
+                            ))),
+                        }?;
+                }
+                Arg {
+                    id: CliArgumentId::color,
+                    value,
+                } => {
+                    led_render_options.lit_color = Color::from_str(value.as_ref().unwrap())
+                        .map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--color '{}': wrong value. specify #rrggbb",
+                                value.as_ref().unwrap()
+                            ))
+                        })?;
+                    if !unlit_color_given {
+                        led_render_options.unlit_color = led_render_options
+                            .background_color
+                            .mix(led_render_options.lit_color, 0.2);
+                    }
+                }
+                Arg {
+                    id: CliArgumentId::L,
+                    value,
+                } => {
+                    let path = Path::new(value.as_ref().unwrap());
+                    let format = if path
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+                    {
+                        LedImageFormat::Svg
+                    } else {
+                        LedImageFormat::Png
+                    };
+                    let file = File::create(path)
+                        .map_err(|e| CliError::BadgeError(BadgeError::FileIo(value.clone(), e)))?;
+                    let writer = BufWriter::new(&file);
+                    badge
+                        .write_led_image(msg_number, format, &led_render_options, writer)
+                        .map_err(|e| match e {
+                            BadgeError::ImageWriteError(None, e) => {
+                                BadgeError::ImageWriteError(value.clone(), e)
+                            }
+                            e => e,
+                        })?;
+                    disable_send_to_badge = true;
+                }
+                Arg {
+                    id: CliArgumentId::dot,
+                    value,
+                } => {
+                    led_render_options.shape = DotShape::from_str(value.as_ref().unwrap())
+                        .map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--dot '{}': wrong value. specify [{}]",
+                                value.as_ref().unwrap(),
+                                DotShape::values()
+                                    .map(|v| v.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(","),
+                            ))
+                        })?;
+                }
+                Arg {
+                    id: CliArgumentId::gap,
+                    value,
+                } => {
+                    led_render_options.gap = match usize::from_str(value.as_ref().unwrap().as_str())
+                    {
+                        Ok(i) if (0..=64).contains(&i) => Ok(i),
+                        _ => Err(CliError::CliError(format!(
+                            "--gap '{}': wrong value. specify 0..64",
+                            value.as_ref().unwrap()
+                        ))),
+                    }?;
+                }
+                Arg {
+                    id: CliArgumentId::unlit_color,
+                    value,
+                } => {
+                    led_render_options.unlit_color = Color::from_str(value.as_ref().unwrap())
+                        .map_err(|_err| {
+                            CliError::CliError(format!(
+                                "--unlit-color '{}': wrong value. specify #rrggbb",
+                                value.as_ref().unwrap()
+                            ))
+                        })?;
+                    unlit_color_given = true;
+                }
+                Arg {
+                    id: CliArgumentId::bezel,
+                    value,
+                } => {
+                    led_render_options.bezel =
+                        match usize::from_str(value.as_ref().unwrap().as_str()) {
+                            Ok(i) if (0..=64).contains(&i) => Ok(i),
+                            _ => Err(CliError::CliError(format!(
+                                "--bezel '{}': wrong value. specify 0..64",
+                                value.as_ref().unwrap()
+                            ))),
+                        }?;
+                }
+                Arg {
+                    id: CliArgumentId::H,
+                    value,
                 } => {
                     let file = File::create(Path::new(&value.as_ref().unwrap()))
                         .map_err(|e| CliError::BadgeError(BadgeError::FileIo(value.clone(), e)))?;
                     let writer = BufWriter::new(&file);
-                    badge.write_to_png(msg_number, writer)?;
+                    badge
+                        .write_html_preview(badge_type.visible_width(), writer)
+                        .map_err(|e| match e {
+                            BadgeError::FileIo(None, e) => BadgeError::FileIo(value.clone(), e),
+                            e => e,
+                        })?;
+                    disable_send_to_badge = true;
+                }
+                Arg {
+                    id: CliArgumentId::O,
+                    value,
+                } => {
+                    let png_path = Path::new(value.as_ref().unwrap());
+                    if png_path
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+                    {
+                        return Err(CliError::CliError(format!(
+                            "-O '{}': wrong value. specify the png file other than json",
+                            value.as_ref().unwrap()
+                        )));
+                    }
+                    let json_path = png_path.with_extension("json");
+                    let json_path_info = Some(json_path.display().to_string());
+                    let png_file = File::create(png_path)
+                        .map_err(|e| CliError::BadgeError(BadgeError::FileIo(value.clone(), e)))?;
+                    let json_file = File::create(&json_path).map_err(|e| {
+                        CliError::BadgeError(BadgeError::FileIo(json_path_info.clone(), e))
+                    })?;
+                    badge
+                        .write_sprite_sheet(BufWriter::new(&png_file), BufWriter::new(&json_file))
+                        .map_err(|e| match e {
+                            BadgeError::ImageWriteError(None, e) => {
+                                BadgeError::ImageWriteError(value.clone(), e)
+                            }
+                            BadgeError::FileIo(None, e) => BadgeError::FileIo(json_path_info, e),
+                            e => e,
+                        })?;
                     disable_send_to_badge = true;
                 }
                 Arg {
@@ -290,8 +1463,26 @@ fn main() {
             }
         }
 
+        if let Some(value) = unused_raw_width {
+            return Err(CliError::CliError(format!(
+                "--raw-width '{}': no effect. specify before --raw or --hex",
+                value
+            )));
+        }
+        if let Some(value) = unused_grid {
+            return Err(CliError::CliError(format!(
+                "--grid '{}': no effect. specify before -p or -A",
+                value
+            )));
+        }
+        if let Some(value) = unused_align_width {
+            return Err(CliError::CliError(format!(
+                "-w '{}': no effect. specify before -a",
+                value
+            )));
+        }
         if !disable_send_to_badge {
-            badge.send(BadgeType::Auto)?;
+            badge.send(badge_type)?;
         }
         Ok(0)
     })()